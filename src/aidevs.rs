@@ -2,7 +2,11 @@ use anyhow::{anyhow, bail};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    fetcher::Fetcher,
+    guardrails::{GuardInput, Guardrails},
+};
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct TokenResponse {
@@ -27,15 +31,10 @@ pub async fn get_task_token(config: &Config, task_name: &str) -> anyhow::Result<
     let mut url = config.api_url.clone();
     url.set_path(&format!("token/{task_name}"));
 
-    let client = reqwest::Client::new();
     let payload = json!({"apikey": config.api_key});
 
-    let response = client
-        .post(url)
-        .json(&payload)
-        .send()
-        .await?
-        .json::<TokenResponse>()
+    let response = Fetcher::from_config(config)?
+        .post_json::<_, TokenResponse>(url, &payload)
         .await?;
 
     if response.code != 0 {
@@ -51,7 +50,9 @@ pub async fn get_hint(config: &Config, task_name: &str) -> anyhow::Result<String
     let mut url = config.api_url.clone();
     url.set_path(&format!("hint/{task_name}"));
 
-    let response = reqwest::get(url).await?.json::<HintResponse>().await?;
+    let response = Fetcher::from_config(config)?
+        .get_json::<HintResponse>(url)
+        .await?;
 
     log::debug!("Hint response: {response:?}");
 
@@ -62,26 +63,53 @@ pub async fn get_task<T: DeserializeOwned>(config: &Config, token: &str) -> anyh
     let mut url = config.api_url.clone();
     url.set_path(&format!("task/{token}"));
 
-    let response = reqwest::get(url).await?.json::<T>().await?;
+    let response = Fetcher::from_config(config)?.get_json::<T>(url).await?;
     Ok(response)
 }
 
+/// Posts task answer after checking it with guardrails configured for all tasks.
+///
+/// * `config`: App configuration
+/// * `token`: Task token
+/// * `payload`: answer payload with `answer` field
 pub async fn post_answer<T: Serialize>(
     config: &Config,
     token: &str,
     payload: &T,
+) -> anyhow::Result<AnswerResponse> {
+    Guardrails::from_config(config)?
+        .verify_payload(&serde_json::to_value(payload)?)
+        .await?;
+    send_answer(config, token, payload).await
+}
+
+/// Posts task answer after checking it with guardrails, using the question and the context
+/// the answer was generated from. Only with them the LLM relevance and consistency checks apply.
+///
+/// * `config`: App configuration
+/// * `token`: Task token
+/// * `payload`: answer payload with `answer` field
+/// * `input`: checked answer with its question and context
+pub async fn post_checked_answer<T: Serialize>(
+    config: &Config,
+    token: &str,
+    payload: &T,
+    input: &GuardInput<'_>,
+) -> anyhow::Result<AnswerResponse> {
+    Guardrails::from_config(config)?.verify(input).await?;
+    send_answer(config, token, payload).await
+}
+
+async fn send_answer<T: Serialize>(
+    config: &Config,
+    token: &str,
+    payload: &T,
 ) -> anyhow::Result<AnswerResponse> {
     let mut url = config.api_url.clone();
     url.set_path(&format!("answer/{token}"));
 
-    let client = reqwest::Client::new();
-
-    let response = client
-        .post(url)
-        .json(payload)
-        .send()
-        .await?
-        .json::<AnswerResponse>()
+    let response = Fetcher::from_config(config)?
+        .post_json::<_, AnswerResponse>(url, payload)
         .await?;
 
     log::debug!("Answer response: {response:?}");
@@ -0,0 +1,325 @@
+mod dictionary;
+mod llm;
+mod patterns;
+
+use std::collections::BTreeMap;
+
+use async_openai::{config::OpenAIConfig, Client};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+use crate::{config::Config, utils};
+
+pub use dictionary::Dictionary;
+pub use patterns::PatternDetector;
+
+/// Model of the optional LLM pass and of prompts sent with `--ask`.
+const LLM_MODEL: &str = "gpt-3.5-turbo";
+
+/// Kind of personal data, with placeholder replacing it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PiiKind {
+    Name,
+    Surname,
+    City,
+    Occupation,
+    Email,
+    Phone,
+    Pesel,
+    BankAccount,
+}
+
+/// Personal data found in a text, `start` and `end` are byte offsets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Text with personal data replaced with numbered placeholders, e.g. `%imie_1%`,
+/// and mapping needed to restore them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Pseudonymized {
+    pub text: String,
+    pub mapping: BTreeMap<String, String>,
+}
+
+/// Detects personal data with regular expressions, dictionaries and optionally LLM.
+pub struct Anonymizer {
+    patterns: PatternDetector,
+    dictionary: Dictionary,
+    llm: Option<Client<OpenAIConfig>>,
+}
+
+#[derive(Debug, Args)]
+pub struct AnonymizeArgs {
+    /// Text to anonymize
+    pub text: String,
+
+    /// Replace personal data with numbered placeholders and print their mapping
+    #[arg(short, long)]
+    pub reversible: bool,
+
+    /// Ask LLM with pseudonymized text and print the answer with personal data restored,
+    /// personal data is detected locally only
+    #[arg(short, long, conflicts_with = "reversible")]
+    pub ask: bool,
+}
+
+impl PiiKind {
+    /// Placeholder used by 'rodo' task.
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Self::Name => "%imie%",
+            Self::Surname => "%nazwisko%",
+            Self::City => "%miasto%",
+            Self::Occupation => "%zawod%",
+            Self::Email => "%email%",
+            Self::Phone => "%telefon%",
+            Self::Pesel => "%pesel%",
+            Self::BankAccount => "%konto%",
+        }
+    }
+
+    /// Placeholder of n-th distinct value of the kind, e.g. `%imie_2%`.
+    fn numbered_placeholder(&self, number: usize) -> String {
+        let placeholder = self.placeholder().trim_end_matches('%');
+        format!("{placeholder}_{number}%")
+    }
+}
+
+impl Pseudonymized {
+    /// Replaces placeholders in the text, e.g. LLM answer, with original values.
+    ///
+    /// * `text`: text with placeholders
+    pub fn restore(&self, text: &str) -> String {
+        self.mapping
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| {
+                text.replace(placeholder, original)
+            })
+    }
+}
+
+impl Anonymizer {
+    pub fn new(
+        patterns: PatternDetector,
+        dictionary: Dictionary,
+        llm: Option<Client<OpenAIConfig>>,
+    ) -> Self {
+        Self {
+            patterns,
+            dictionary,
+            llm,
+        }
+    }
+
+    /// Creates anonymizer with dictionary and LLM pass from configuration.
+    /// Missing dictionary file leaves only regular expressions and LLM detection.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let dictionary = match config.anonymizer_dictionary.exists() {
+            true => Dictionary::from_file(&config.anonymizer_dictionary)?,
+            false => Dictionary::default(),
+        };
+        let llm = config
+            .anonymizer_llm
+            .then(|| Client::with_config(OpenAIConfig::default()));
+
+        Ok(Self::new(PatternDetector::new()?, dictionary, llm))
+    }
+
+    /// Finds personal data, sorted by position and without overlapping.
+    /// Longer detections win over shorter ones starting at the same position.
+    /// The LLM pass, when enabled, receives the raw text.
+    ///
+    /// * `text`: text to search
+    pub async fn detect(&self, text: &str) -> anyhow::Result<Vec<Detection>> {
+        let mut detections = self.detect_locally(text);
+        if let Some(client) = &self.llm {
+            detections.extend(llm::detect(client, LLM_MODEL, text).await?);
+        }
+        Ok(without_overlaps(detections))
+    }
+
+    /// Finds personal data with regular expressions and dictionaries only.
+    fn detect_locally(&self, text: &str) -> Vec<Detection> {
+        let mut detections = self.patterns.detect(text);
+        detections.extend(self.dictionary.detect(text));
+        detections
+    }
+
+    /// Replaces personal data with placeholders, irreversibly.
+    ///
+    /// * `text`: text to anonymize
+    pub async fn anonymize(&self, text: &str) -> anyhow::Result<String> {
+        let detections = self.detect(text).await?;
+        Ok(replace(text, &detections, |d, _| {
+            d.kind.placeholder().to_string()
+        }))
+    }
+
+    /// Replaces personal data with numbered placeholders, the same value gets the same placeholder.
+    ///
+    /// * `text`: text to pseudonymize
+    pub async fn pseudonymize(&self, text: &str) -> anyhow::Result<Pseudonymized> {
+        let detections = self.detect(text).await?;
+        Ok(pseudonymize_detections(text, &detections))
+    }
+
+    /// Asks LLM with personal data in the question and the context pseudonymized,
+    /// and restores it in the answer. Personal data is detected locally only,
+    /// even with the LLM pass enabled, so no raw text is sent to the API.
+    ///
+    /// * `client`: OpenAI client
+    /// * `model`: LLM model
+    /// * `question`: question with personal data
+    /// * `context`: system message
+    pub async fn ask_llm(
+        &self,
+        client: &Client<OpenAIConfig>,
+        model: &str,
+        question: &str,
+        context: Option<&str>,
+    ) -> anyhow::Result<String> {
+        // Both texts are pseudonymized together to share placeholders
+        let separator = "\n\u{1e}\n";
+        let combined = format!("{}{separator}{question}", context.unwrap_or_default());
+        let detections = without_overlaps(self.detect_locally(&combined));
+        let pseudonymized = pseudonymize_detections(&combined, &detections);
+        let (context, question) = pseudonymized
+            .text
+            .split_once(separator)
+            .unwrap_or(("", &pseudonymized.text));
+        let context = (!context.is_empty()).then_some(context);
+
+        let answer = utils::ask_llm(client, model, question, context).await?;
+        Ok(pseudonymized.restore(&answer))
+    }
+}
+
+/// Sorts detections by position and drops the ones overlapping longer or earlier detections.
+fn without_overlaps(mut detections: Vec<Detection>) -> Vec<Detection> {
+    detections.sort_by_key(|d| (d.start, std::cmp::Reverse(d.end)));
+    let mut end = 0;
+    detections.retain(|d| {
+        let keep = d.start >= end;
+        if keep {
+            end = d.end;
+        }
+        keep
+    });
+    detections
+}
+
+/// Replaces detections with numbered placeholders, the same value gets the same placeholder.
+///
+/// * `text`: source text
+/// * `detections`: sorted, not overlapping detections
+fn pseudonymize_detections(text: &str, detections: &[Detection]) -> Pseudonymized {
+    let mut placeholders = BTreeMap::<(PiiKind, &str), String>::new();
+    let mut counts = BTreeMap::<PiiKind, usize>::new();
+    let mut mapping = BTreeMap::new();
+
+    let text = replace(text, detections, |detection, original| {
+        placeholders
+            .entry((detection.kind, original))
+            .or_insert_with(|| {
+                let count = counts.entry(detection.kind).or_default();
+                *count += 1;
+                let placeholder = detection.kind.numbered_placeholder(*count);
+                mapping.insert(placeholder.clone(), original.to_string());
+                placeholder
+            })
+            .clone()
+    });
+
+    Pseudonymized { text, mapping }
+}
+
+/// Replaces detections in the text.
+///
+/// * `text`: source text
+/// * `detections`: sorted, not overlapping detections
+/// * `replacement`: replacement of the detection and its original text
+fn replace<'a>(
+    text: &'a str,
+    detections: &[Detection],
+    mut replacement: impl FnMut(&Detection, &'a str) -> String,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for detection in detections {
+        result.push_str(&text[position..detection.start]);
+        result.push_str(&replacement(
+            detection,
+            &text[detection.start..detection.end],
+        ));
+        position = detection.end;
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+/// Anonymizes text from command line.
+///
+/// * `config`: App configuration
+/// * `args`: command arguments
+pub async fn run(config: &Config, args: AnonymizeArgs) -> anyhow::Result<()> {
+    let anonymizer = Anonymizer::from_config(config)?;
+
+    match (args.reversible, args.ask) {
+        (_, true) => {
+            let client = Client::with_config(OpenAIConfig::default());
+            let answer = anonymizer
+                .ask_llm(&client, LLM_MODEL, &args.text, None)
+                .await?;
+            println!("{answer}");
+        }
+        (true, false) => {
+            let pseudonymized = anonymizer.pseudonymize(&args.text).await?;
+            println!("{}", pseudonymized.text);
+            for (placeholder, original) in &pseudonymized.mapping {
+                println!("{placeholder} = {original}");
+            }
+        }
+        (false, false) => println!("{}", anonymizer.anonymize(&args.text).await?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_anonymize_and_restore() {
+        let dictionary = Dictionary::from_file("anonymizer_dictionary.json").unwrap();
+        let anonymizer = Anonymizer::new(PatternDetector::new().unwrap(), dictionary, None);
+
+        let text = "Nazywam się Adam Nowak, mieszkam w Krakowie i pracuję jako programista. \
+            Mój PESEL to 44051401359, mail adam.nowak@example.com, tel. 601-234-567. \
+            Adama można też spotkać w Warszawie.";
+        assert_eq!(
+            anonymizer.anonymize(text).await.unwrap(),
+            "Nazywam się %imie% %nazwisko%, mieszkam w %miasto% i pracuję jako %zawod%. \
+            Mój PESEL to %pesel%, mail %email%, tel. %telefon%. \
+            %imie% można też spotkać w %miasto%."
+        );
+
+        let pseudonymized = anonymizer.pseudonymize(text).await.unwrap();
+        assert!(pseudonymized.text.starts_with(
+            "Nazywam się %imie_1% %nazwisko_1%, mieszkam w %miasto_1% i pracuję jako %zawod_1%."
+        ));
+        assert!(pseudonymized.text.ends_with("w %miasto_2%."));
+        assert_eq!(pseudonymized.restore(&pseudonymized.text), text);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{fs, path::Path};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use super::{Detection, PiiKind};
+
+/// Inflection endings of Polish nouns and English plural, accepted after an entry stem.
+const ENDINGS: &[&str] = &[
+    "a", "e", "i", "o", "u", "y", "ą", "ę", "s", "em", "om", "ie", "iu", "ii", "ią", "ię", "ej",
+    "im", "ów", "es", "ach", "ami", "ego", "emu", "owi", "iem", "iej", "owie", "iego",
+];
+/// Entries with shorter stems are matched only without inflection.
+const MIN_STEM: usize = 3;
+
+/// Words of personal data, in JSON file with `names`, `surnames`, `cities` and `occupations` lists.
+#[derive(Debug, Default, Deserialize)]
+struct DictionaryFile {
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    surnames: Vec<String>,
+    #[serde(default)]
+    cities: Vec<String>,
+    #[serde(default)]
+    occupations: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    kind: PiiKind,
+    /// Forms of each word of the entry, base form first
+    words: Vec<Vec<String>>,
+}
+
+/// Detects names, surnames, cities and occupations from dictionaries, also in inflected forms,
+/// e.g. `Krakowie` for `Kraków`. Names, surnames and cities must be capitalized.
+/// A capitalized word directly after a name is treated as a surname.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    entries: Vec<Entry>,
+}
+
+struct Token<'a> {
+    word: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl Dictionary {
+    /// Loads dictionary from JSON file.
+    ///
+    /// * `path`: path to the dictionary file
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let file: DictionaryFile = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid anonymizer dictionary {}: {e}", path.display()))?;
+
+        let entries = [
+            (PiiKind::Name, file.names),
+            (PiiKind::Surname, file.surnames),
+            (PiiKind::City, file.cities),
+            (PiiKind::Occupation, file.occupations),
+        ]
+        .into_iter()
+        .flat_map(|(kind, words)| words.into_iter().map(move |entry| Entry::new(kind, &entry)))
+        .collect::<Vec<_>>();
+        log::debug!("Loaded {} anonymizer dictionary entries", entries.len());
+
+        Ok(Self { entries })
+    }
+
+    /// Checks if the word in base form is a single word entry of the kind, ignoring case.
+    ///
+    /// * `kind`: kind of the entry
+    /// * `word`: word to check
+    pub fn contains(&self, kind: PiiKind, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.entries
+            .iter()
+            .any(|e| e.kind == kind && e.words.len() == 1 && e.words[0][0] == word)
+    }
+
+    /// Finds dictionary words, the longest entry wins at each position.
+    ///
+    /// * `text`: text to search
+    pub fn detect(&self, text: &str) -> Vec<Detection> {
+        let tokens = tokenize(text);
+        let mut detections = Vec::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            let found = self
+                .entries
+                .iter()
+                .filter(|entry| entry.matches(&tokens[index..]))
+                .min_by_key(|entry| std::cmp::Reverse(entry.words.len()));
+            if let Some(entry) = found {
+                let last = index + entry.words.len() - 1;
+                detections.push(Detection {
+                    kind: entry.kind,
+                    start: tokens[index].start,
+                    end: tokens[last].end,
+                });
+                index = last + 1;
+
+                if entry.kind == PiiKind::Name {
+                    if let Some(surname) = tokens.get(index).filter(|next| {
+                        is_capitalized(next.word)
+                            && text[tokens[last].end..next.start].trim().is_empty()
+                            && !self.entries.iter().any(|e| e.matches(&tokens[index..]))
+                    }) {
+                        detections.push(Detection {
+                            kind: PiiKind::Surname,
+                            start: surname.start,
+                            end: surname.end,
+                        });
+                        index += 1;
+                    }
+                }
+            } else {
+                index += 1;
+            }
+        }
+        detections
+    }
+}
+
+impl Entry {
+    fn new(kind: PiiKind, entry: &str) -> Self {
+        let words = entry
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+            .map(|word| word_forms(&word.to_lowercase()))
+            .collect();
+        Self { kind, words }
+    }
+
+    /// Checks if the entry matches tokens at the beginning of the slice.
+    fn matches(&self, tokens: &[Token]) -> bool {
+        if self.words.is_empty() || tokens.len() < self.words.len() {
+            return false;
+        }
+        let capitalized = matches!(self.kind, PiiKind::Name | PiiKind::Surname | PiiKind::City);
+        self.words.iter().zip(tokens).all(|(forms, token)| {
+            let word = token.word.to_lowercase();
+            (!capitalized || is_capitalized(token.word))
+                && (word == forms[0]
+                    || forms[1..].iter().any(|stem| {
+                        word.strip_prefix(stem.as_str())
+                            .is_some_and(|ending| ENDINGS.contains(&ending))
+                    }))
+        })
+    }
+}
+
+/// Base form and stems of the word, with common Polish alternations, e.g. `kraków` -> `krakow`.
+fn word_forms(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_string()];
+    let stem = word.trim_end_matches(['a', 'e', 'i', 'o', 'u', 'y', 'ą', 'ę']);
+    if stem.chars().count() < MIN_STEM {
+        return forms;
+    }
+
+    let mut alternated = stem.to_string();
+    if let Some(position) = alternated.rfind('ó') {
+        alternated.replace_range(position..position + 'ó'.len_utf8(), "o");
+    }
+    for (from, to) in [
+        ("ń", "n"),
+        ("ś", "s"),
+        ("ć", "c"),
+        ("ź", "z"),
+        ("ek", "k"),
+        ("ec", "c"),
+    ] {
+        if let Some(base) = alternated.strip_suffix(from) {
+            alternated = format!("{base}{to}");
+        }
+    }
+
+    forms.push(stem.to_string());
+    if alternated != stem {
+        forms.push(alternated);
+    }
+    forms
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphabetic(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(begin)) => {
+                tokens.push(Token {
+                    word: &text[begin..index],
+                    start: begin,
+                    end: index,
+                });
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    tokens
+}
+
+fn is_capitalized(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
@@ -0,0 +1,63 @@
+use async_openai::{config::OpenAIConfig, Client};
+use serde::Deserialize;
+
+use super::{Detection, PiiKind};
+use crate::utils;
+
+const DETECT_CONTEXT: &str = r#"You are a detector of personal data in Polish and English texts.
+Find first names, surnames, cities and occupations of people, exactly as they are written in the text, including inflected forms.
+Return JSON object only: {"entities": [{"kind": "name" | "surname" | "city" | "occupation", "text": "exact fragment of the text"}]}"#;
+
+#[derive(Debug, Deserialize)]
+struct Entities {
+    entities: Vec<Entity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entity {
+    kind: PiiKind,
+    text: String,
+}
+
+/// Finds personal data missing in dictionaries with LLM. Each returned fragment
+/// is detected at all its whole-word occurrences, fragments not present in the text are ignored.
+///
+/// * `client`: OpenAI client
+/// * `model`: LLM model
+/// * `text`: text to search
+pub(super) async fn detect(
+    client: &Client<OpenAIConfig>,
+    model: &str,
+    text: &str,
+) -> anyhow::Result<Vec<Detection>> {
+    let answer = utils::ask_llm(client, model, text, Some(DETECT_CONTEXT)).await?;
+    let entities: Entities = utils::parse_json_answer(&answer)?;
+    log::debug!(
+        "LLM found {} personal data entities",
+        entities.entities.len()
+    );
+
+    let detections = entities
+        .entities
+        .iter()
+        .filter(|entity| !entity.text.trim().is_empty())
+        .flat_map(|entity| {
+            let fragment = entity.text.trim();
+            text.match_indices(fragment)
+                .filter(|(start, _)| is_whole_word(text, *start, start + fragment.len()))
+                .map(|(start, _)| Detection {
+                    kind: entity.kind,
+                    start,
+                    end: start + fragment.len(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    Ok(detections)
+}
+
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
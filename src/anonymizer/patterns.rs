@@ -0,0 +1,67 @@
+use regex::Regex;
+
+use super::{Detection, PiiKind};
+
+const PESEL_WEIGHTS: [u32; 10] = [1, 3, 7, 9, 1, 3, 7, 9, 1, 3];
+
+/// Detects personal data with fixed format: e-mail addresses, phone numbers,
+/// PESEL numbers and bank account numbers.
+pub struct PatternDetector {
+    patterns: Vec<(PiiKind, Regex)>,
+}
+
+impl PatternDetector {
+    pub fn new() -> anyhow::Result<Self> {
+        let patterns = [
+            (PiiKind::Email, r"[\w.+-]+@[\w-]+(\.[\w-]+)+"),
+            (
+                PiiKind::Phone,
+                r"(\+48[\s-]?)?\b\d{3}[\s-]?\d{3}[\s-]?\d{3}\b",
+            ),
+            (PiiKind::Pesel, r"\b\d{11}\b"),
+            (PiiKind::BankAccount, r"\b(PL)?\d{2}(\s?\d{4}){6}\b"),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| Ok((kind, Regex::new(pattern)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// Finds all matches, possibly overlapping. 11 digits numbers are detected
+    /// only with valid PESEL control digit.
+    ///
+    /// * `text`: text to search
+    pub fn detect(&self, text: &str) -> Vec<Detection> {
+        self.patterns
+            .iter()
+            .flat_map(|(kind, pattern)| {
+                pattern
+                    .find_iter(text)
+                    .filter(|m| *kind != PiiKind::Pesel || is_valid_pesel(m.as_str()))
+                    .map(|m| Detection {
+                        kind: *kind,
+                        start: m.start(),
+                        end: m.end(),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Validates PESEL control digit, to not treat any 11 digits number as personal data.
+fn is_valid_pesel(number: &str) -> bool {
+    let digits = number
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .collect::<Vec<_>>();
+    if digits.len() != 11 {
+        return false;
+    }
+    let sum = PESEL_WEIGHTS
+        .iter()
+        .zip(&digits)
+        .map(|(weight, digit)| weight * digit)
+        .sum::<u32>();
+    (10 - sum % 10) % 10 == digits[10]
+}
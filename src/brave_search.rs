@@ -1,20 +1,80 @@
+use anyhow::{anyhow, bail};
+use clap::{Args, ValueEnum};
 use reqwest::{
     header::{self, HeaderMap, HeaderValue},
     Client, ClientBuilder,
 };
-use serde::Deserialize;
-use serde_with::{serde_as, NoneAsEmptyString};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::{serde_as, DefaultOnNull, NoneAsEmptyString};
+use strum_macros::{Display, EnumString};
 use url::Url;
 
+use crate::config::Config;
+
 enum BraveSearchHeader {
     SubscriptionToken,
     LocCountry,
+    RateLimitLimit,
+    RateLimitPolicy,
+    RateLimitRemaining,
+    RateLimitReset,
+}
+
+/// Time range of discovered results: past day, week, month or year.
+#[derive(Debug, Clone, Copy, Display, EnumString)]
+pub enum Freshness {
+    #[strum(serialize = "pd")]
+    Day,
+    #[strum(serialize = "pw")]
+    Week,
+    #[strum(serialize = "pm")]
+    Month,
+    #[strum(serialize = "py")]
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+/// Optional query parameters of Brave Search API endpoints.
+#[derive(Debug, Clone, Default)]
+pub struct BraveSearchParams {
+    /// Number of results per page (max 20 for web and news, 100 for images)
+    pub count: Option<u8>,
+    /// Zero based page index (max 9)
+    pub offset: Option<u8>,
+    pub search_lang: Option<String>,
+    pub freshness: Option<Freshness>,
+    pub safesearch: Option<SafeSearch>,
+}
+
+/// Values of `X-RateLimit-*` response headers.
+/// Each list contains values for every active policy window (e.g. per second and per month).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BraveRateLimit {
+    pub limit: Vec<u64>,
+    pub policy: Option<String>,
+    pub remaining: Vec<u64>,
+    pub reset: Vec<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BraveSearchResult<T> {
+    pub response: T,
+    pub rate_limit: BraveRateLimit,
 }
 
 #[serde_as]
-#[derive(Default, Debug, Deserialize)]
+#[derive(Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct BraveSearchResponseQuery {
     pub original: String,
+    pub altered: Option<String>,
     pub show_strict_warning: bool,
     pub is_navigational: bool,
     pub is_news_breaking: bool,
@@ -34,7 +94,8 @@ pub struct BraveSearchResponseQuery {
     pub state: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Profile {
     pub name: String,
     pub url: String,
@@ -42,7 +103,8 @@ pub struct Profile {
     pub img: String,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct MetaUrl {
     pub scheme: String,
     pub netloc: String,
@@ -51,44 +113,138 @@ pub struct MetaUrl {
     pub path: String,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Thumbnail {
     pub src: String,
-    pub original: String,
+    pub original: Option<String>,
     pub logo: bool,
 }
 
 #[serde_as]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct SearchResultItem {
     pub title: String,
     pub url: Url,
+    #[serde(default)]
     pub is_source_local: bool,
+    #[serde(default)]
     pub is_source_both: bool,
+    #[serde(default)]
     pub description: String,
     pub page_age: Option<String>,
-    pub profile: Profile,
-    pub language: String,
+    pub profile: Option<Profile>,
+    pub language: Option<String>,
+    #[serde(default)]
     pub family_friendly: bool,
+    #[serde(default)]
     pub r#type: String,
     #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
     pub subtype: Option<String>,
     pub meta_url: Option<MetaUrl>,
     pub thumbnail: Option<Thumbnail>,
     pub age: Option<String>,
+    #[serde_as(as = "DefaultOnNull")]
+    #[serde(default)]
+    pub extra_snippets: Vec<String>,
 }
 
-#[derive(Default, Debug, Deserialize)]
+#[derive(Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct SearchResult {
     pub r#type: String,
     pub results: Vec<SearchResultItem>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct BraveSearchResponse {
+    #[serde(default)]
     pub query: BraveSearchResponseQuery,
     pub r#type: String,
+    #[serde(default)]
     pub web: SearchResult,
+    #[serde(default)]
+    pub news: SearchResult,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewsResultItem {
+    pub title: String,
+    pub url: Url,
+    #[serde(default)]
+    pub description: String,
+    pub age: Option<String>,
+    pub page_age: Option<String>,
+    #[serde(default)]
+    pub breaking: bool,
+    pub meta_url: Option<MetaUrl>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BraveNewsResponse {
+    #[serde(default)]
+    pub query: BraveSearchResponseQuery,
+    pub r#type: String,
+    #[serde(default)]
+    pub results: Vec<NewsResultItem>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImageProperties {
+    pub url: Option<Url>,
+    pub placeholder: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImageResultItem {
+    #[serde(default)]
+    pub title: String,
+    pub url: Url,
+    #[serde(default)]
+    pub source: String,
+    pub page_fetched: Option<String>,
+    pub thumbnail: Option<Thumbnail>,
+    #[serde(default)]
+    pub properties: ImageProperties,
+    pub meta_url: Option<MetaUrl>,
+    pub confidence: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BraveImagesResponse {
+    #[serde(default)]
+    pub query: BraveSearchResponseQuery,
+    pub r#type: String,
+    #[serde(default)]
+    pub results: Vec<ImageResultItem>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SearchKind {
+    Web,
+    News,
+    Images,
+}
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Search query
+    pub query: String,
+
+    /// Kind of results
+    #[arg(short, long, value_enum, default_value_t = SearchKind::Web)]
+    pub kind: SearchKind,
+
+    /// Number of results
+    #[arg(short, long)]
+    pub count: Option<u8>,
+
+    /// Zero based page index
+    #[arg(short, long)]
+    pub offset: Option<u8>,
 }
 
 pub struct BraveSearchClient {
@@ -101,7 +257,75 @@ impl BraveSearchHeader {
         match self {
             Self::SubscriptionToken => "X-Subscription-Token",
             Self::LocCountry => "X-Loc-Country",
+            Self::RateLimitLimit => "X-RateLimit-Limit",
+            Self::RateLimitPolicy => "X-RateLimit-Policy",
+            Self::RateLimitRemaining => "X-RateLimit-Remaining",
+            Self::RateLimitReset => "X-RateLimit-Reset",
+        }
+    }
+}
+
+impl From<&Config> for BraveSearchParams {
+    fn from(config: &Config) -> Self {
+        Self {
+            search_lang: config.brave_search_lang.clone(),
+            freshness: config.brave_search_freshness,
+            safesearch: config.brave_search_safesearch,
+            ..Default::default()
+        }
+    }
+}
+
+impl BraveSearchParams {
+    fn query_pairs(&self, query: &str) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![("q", query.to_string())];
+        if let Some(count) = self.count {
+            pairs.push(("count", count.to_string()));
+        }
+        if let Some(offset) = self.offset {
+            pairs.push(("offset", offset.to_string()));
+        }
+        if let Some(search_lang) = self.search_lang.as_ref().filter(|l| !l.is_empty()) {
+            pairs.push(("search_lang", search_lang.clone()));
         }
+        if let Some(freshness) = self.freshness {
+            pairs.push(("freshness", freshness.to_string()));
+        }
+        if let Some(safesearch) = self.safesearch {
+            pairs.push(("safesearch", safesearch.to_string()));
+        }
+        pairs
+    }
+}
+
+impl BraveRateLimit {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let values = |h: BraveSearchHeader| {
+            headers
+                .get(h.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(|v| {
+                    v.split(',')
+                        .filter_map(|n| n.trim().parse().ok())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        Self {
+            limit: values(BraveSearchHeader::RateLimitLimit),
+            policy: headers
+                .get(BraveSearchHeader::RateLimitPolicy.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+            remaining: values(BraveSearchHeader::RateLimitRemaining),
+            reset: values(BraveSearchHeader::RateLimitReset),
+        }
+    }
+
+    /// Returns `true` when any of the rate limit windows is used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining.contains(&0)
     }
 }
 
@@ -122,6 +346,19 @@ impl BraveSearchClient {
         Ok(Self { client, headers })
     }
 
+    /// Creates client with API key from configuration, results are localized to Poland.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let api_key = config
+            .brave_search_api_key
+            .as_ref()
+            .ok_or(anyhow!("Brave Search API key not found in configuration"))?;
+        let mut client = Self::new(api_key)?;
+        client.set_country("PL")?;
+        Ok(client)
+    }
+
     pub fn set_country(&mut self, country_code: &str) -> anyhow::Result<()> {
         self.headers.insert(
             BraveSearchHeader::LocCountry.as_str(),
@@ -130,10 +367,39 @@ impl BraveSearchClient {
         Ok(())
     }
 
-    pub async fn search(&self, query: &str) -> anyhow::Result<BraveSearchResponse> {
+    pub async fn search(
+        &self,
+        query: &str,
+        params: &BraveSearchParams,
+    ) -> anyhow::Result<BraveSearchResult<BraveSearchResponse>> {
+        self.get("web/search", query, params).await
+    }
+
+    pub async fn news(
+        &self,
+        query: &str,
+        params: &BraveSearchParams,
+    ) -> anyhow::Result<BraveSearchResult<BraveNewsResponse>> {
+        self.get("news/search", query, params).await
+    }
+
+    pub async fn images(
+        &self,
+        query: &str,
+        params: &BraveSearchParams,
+    ) -> anyhow::Result<BraveSearchResult<BraveImagesResponse>> {
+        self.get("images/search", query, params).await
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &str,
+        params: &BraveSearchParams,
+    ) -> anyhow::Result<BraveSearchResult<T>> {
         let url = Url::parse_with_params(
-            &format!("{}/web/search", Self::API_BASE_URL),
-            &[("q", query)],
+            &format!("{}/{endpoint}", Self::API_BASE_URL),
+            params.query_pairs(query),
         )?;
 
         let response = self
@@ -143,7 +409,129 @@ impl BraveSearchClient {
             .send()
             .await?;
 
-        let results: BraveSearchResponse = response.json().await?;
-        Ok(results)
+        let rate_limit = BraveRateLimit::from_headers(response.headers());
+        log::debug!("Brave Search rate limit: {rate_limit:?}");
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("Brave Search API error [{status}]: {body}");
+        }
+
+        let response: T = response.json().await?;
+        Ok(BraveSearchResult {
+            response,
+            rate_limit,
+        })
+    }
+}
+
+/// Searches with Brave Search API from command line and prints the response with rate limits as JSON.
+///
+/// * `config`: App configuration
+/// * `args`: command arguments
+pub async fn run(config: &Config, args: SearchArgs) -> anyhow::Result<()> {
+    let client = BraveSearchClient::from_config(config)?;
+    let params = BraveSearchParams {
+        count: args.count,
+        offset: args.offset,
+        ..BraveSearchParams::from(config)
+    };
+
+    let output = match args.kind {
+        SearchKind::Web => {
+            serde_json::to_string_pretty(&client.search(&args.query, &params).await?)
+        }
+        SearchKind::News => serde_json::to_string_pretty(&client.news(&args.query, &params).await?),
+        SearchKind::Images => {
+            serde_json::to_string_pretty(&client.images(&args.query, &params).await?)
+        }
+    }?;
+    println!("{output}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_web_search_response() {
+        let content = fs::read_to_string("fixtures/brave_search.json").unwrap();
+        let response: BraveSearchResponse = serde_json::from_str(&content).unwrap();
+
+        assert_eq!(response.query.original, "najwyższy szczyt Polski");
+        assert_eq!(response.query.country, None);
+        assert!(response.news.results.is_empty());
+
+        let [full, partial] = response.web.results.as_slice() else {
+            panic!("Expected two results");
+        };
+        assert_eq!(full.profile.as_ref().unwrap().name, "Wikipedia");
+        assert_eq!(full.subtype.as_deref(), Some("generic"));
+        assert_eq!(full.extra_snippets, ["Wysokość: 2499 m n.p.m."]);
+
+        assert_eq!(partial.profile, None);
+        assert_eq!(partial.description, "");
+        assert_eq!(partial.subtype, None);
+        assert_eq!(partial.meta_url, None);
+        assert!(partial.extra_snippets.is_empty());
+        assert_eq!(partial.thumbnail.as_ref().unwrap().original, None);
+    }
+
+    #[test]
+    fn test_deserialize_news_response() {
+        let content = fs::read_to_string("fixtures/brave_news.json").unwrap();
+        let response: BraveNewsResponse = serde_json::from_str(&content).unwrap();
+
+        assert_eq!(response.r#type, "news");
+        let [full, partial] = response.results.as_slice() else {
+            panic!("Expected two results");
+        };
+        assert!(full.breaking);
+        assert_eq!(full.meta_url.as_ref().unwrap().favicon, "");
+        assert_eq!(full.age.as_deref(), Some("2 days ago"));
+
+        assert!(!partial.breaking);
+        assert_eq!(partial.description, "");
+        assert_eq!(partial.page_age, None);
+        assert_eq!(partial.thumbnail, None);
+    }
+
+    #[test]
+    fn test_deserialize_images_response() {
+        let content = fs::read_to_string("fixtures/brave_images.json").unwrap();
+        let response: BraveImagesResponse = serde_json::from_str(&content).unwrap();
+
+        assert!(response.query.spellcheck_off);
+        let [full, partial] = response.results.as_slice() else {
+            panic!("Expected two results");
+        };
+        assert_eq!(
+            full.properties.url.as_ref().map(Url::as_str),
+            Some("https://gory.example.com/images/rysy.jpg")
+        );
+        assert_eq!(full.confidence.as_deref(), Some("high"));
+
+        assert_eq!(partial.title, "");
+        assert_eq!(partial.source, "");
+        assert_eq!(partial.properties, ImageProperties::default());
+        assert_eq!(partial.meta_url, None);
+    }
+
+    #[test]
+    fn test_query_pairs_skip_empty_language() {
+        let params = BraveSearchParams {
+            count: Some(5),
+            search_lang: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.query_pairs("Rysy"),
+            [("q", "Rysy".to_string()), ("count", "5".to_string())]
+        );
     }
 }
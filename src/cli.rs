@@ -1,6 +1,10 @@
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, Subcommand};
 
-use crate::tasks::Task;
+use crate::{
+    anonymizer::AnonymizeArgs, brave_search::SearchArgs, exchange_rate::RateArgs,
+    render_form::RenderFormArgs, serve::ServeArgs, tasks::Task, transcribe::TranscribeArgs,
+    vision::DescribeImageArgs,
+};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -10,5 +14,44 @@ pub(super) struct Cli {
     pub hint: bool,
 
     #[command(subcommand)]
-    pub task: Task,
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum Command {
+    #[command(flatten)]
+    Task(Task),
+
+    /// serve assistant APIs permanently, without a task token
+    Serve(ServeArgs),
+
+    /// transcribe audio file or URL
+    Transcribe(TranscribeArgs),
+
+    /// ask vision model about image files or URLs
+    DescribeImage(DescribeImageArgs),
+
+    /// manage RenderForm templates and renders
+    RenderForm(RenderFormArgs),
+
+    /// show NBP currency rates or convert between currencies
+    Rate(RateArgs),
+
+    /// replace personal data in text with placeholders
+    Anonymize(AnonymizeArgs),
+
+    /// search web, news or images with Brave Search API
+    BraveSearch(SearchArgs),
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
 }
@@ -1,6 +1,21 @@
+use std::path::PathBuf;
+
 use envconfig::Envconfig;
 use url::Url;
 
+use crate::{
+    brave_search::{Freshness, SafeSearch},
+    data_source::DataSourceKind,
+    guardrails::{GuardrailMode, Language},
+    meme_renderer::MemeBackend,
+    memory::MemoryBackend,
+    moderation::ModerationBackend,
+    organizer::TodoBackend,
+    search_provider::SearchBackend,
+    transcribe::TranscriptionBackendKind,
+    tunnel::TunnelBackend,
+};
+
 #[derive(Debug, Envconfig)]
 pub(crate) struct Config {
     #[envconfig(from = "AI_DEVS2_API_URL")]
@@ -13,8 +28,155 @@ pub(crate) struct Config {
     pub api_listen_address: Option<String>,
     #[envconfig(from = "API_TUNNEL_URL")]
     pub api_tunnel_url: Option<Url>,
+    /// One of: static, local, ngrok, cloudflared, ssh
+    #[envconfig(from = "TUNNEL_PROVIDER")]
+    pub tunnel_provider: Option<TunnelBackend>,
+    /// Path to tunnel binary, when not available in PATH
+    #[envconfig(from = "TUNNEL_BINARY")]
+    pub tunnel_binary: Option<String>,
+    #[envconfig(from = "TUNNEL_SSH_HOST", default = "nokey@localhost.run")]
+    pub tunnel_ssh_host: String,
+    #[envconfig(from = "API_MAX_BODY_SIZE", default = "65536")]
+    pub api_max_body_size: usize,
+    /// Abort API request handling after this many seconds, 0 disables the deadline
+    #[envconfig(from = "API_REQUEST_TIMEOUT_SECS", default = "60")]
+    pub api_request_timeout_secs: u64,
+    /// Stop API server after handling this many requests
+    #[envconfig(from = "API_SHUTDOWN_AFTER")]
+    pub api_shutdown_after: Option<usize>,
+    /// Drop conversation sessions not used for this many seconds
+    #[envconfig(from = "SESSION_TTL_SECS", default = "1800")]
+    pub session_ttl_secs: u64,
+    /// One of: qdrant, file
+    #[envconfig(from = "MEMORY_BACKEND")]
+    pub memory_backend: Option<MemoryBackend>,
+    #[envconfig(from = "MEMORY_FILE", default = "memory.json")]
+    pub memory_file: PathBuf,
+    #[envconfig(from = "MEMORY_COLLECTION", default = "ownapipro_memory")]
+    pub memory_collection: String,
     #[envconfig(from = "RENDER_FORM_API_KEY")]
     pub render_form_api_key: Option<String>,
+    /// One of: renderform, local
+    #[envconfig(from = "MEME_BACKEND", default = "renderform")]
+    pub meme_backend: MemeBackend,
+    /// JSON file with local meme templates
+    #[envconfig(from = "MEME_TEMPLATES", default = "meme_templates.json")]
+    pub meme_templates: PathBuf,
+    #[envconfig(from = "MEME_TEMPLATE", default = "default")]
+    pub meme_template: String,
+    /// Directory with locally rendered memes, hosted by the API server
+    #[envconfig(from = "MEME_OUTPUT_DIR", default = "memes")]
+    pub meme_output_dir: PathBuf,
     #[envconfig(from = "BRAVE_SEARCH_API_KEY")]
     pub brave_search_api_key: Option<String>,
+    #[envconfig(from = "BRAVE_SEARCH_LANG")]
+    pub brave_search_lang: Option<String>,
+    /// One of: pd, pw, pm, py
+    #[envconfig(from = "BRAVE_SEARCH_FRESHNESS")]
+    pub brave_search_freshness: Option<Freshness>,
+    /// One of: off, moderate, strict
+    #[envconfig(from = "BRAVE_SEARCH_SAFESEARCH")]
+    pub brave_search_safesearch: Option<SafeSearch>,
+    /// One of: brave, searxng, offline
+    #[envconfig(from = "SEARCH_PROVIDER")]
+    pub search_provider: Option<SearchBackend>,
+    #[envconfig(from = "SEARXNG_URL")]
+    pub searxng_url: Option<Url>,
+    #[envconfig(from = "SEARCH_FIXTURES_PATH")]
+    pub search_fixtures_path: Option<PathBuf>,
+    /// Pass page content of search results to LLM when selecting the best one
+    #[envconfig(from = "SEARCH_FETCH_PAGES", default = "false")]
+    pub search_fetch_pages: bool,
+    /// Comma separated knowledge task tools: country, currency_rate, currency_conversion, date, llm
+    #[envconfig(
+        from = "KNOWLEDGE_TOOLS",
+        default = "country,currency_rate,currency_conversion,date,llm"
+    )]
+    pub knowledge_tools: String,
+    /// One of: http, fixtures
+    #[envconfig(from = "KNOWLEDGE_DATA_SOURCE", default = "http")]
+    pub knowledge_data_source: DataSourceKind,
+    #[envconfig(from = "KNOWLEDGE_FIXTURES_PATH", default = "fixtures/knowledge.json")]
+    pub knowledge_fixtures_path: PathBuf,
+    /// Cache of downloaded NBP exchange rate tables
+    #[envconfig(from = "EXCHANGE_RATE_CACHE", default = "nbp_cache.json")]
+    pub exchange_rate_cache: PathBuf,
+    /// One of: openai, local
+    #[envconfig(from = "MODERATION_BACKEND", default = "openai")]
+    pub moderation_backend: ModerationBackend,
+    /// JSON file with local pre-filter rules
+    #[envconfig(from = "MODERATION_RULES", default = "moderation_rules.json")]
+    pub moderation_rules: PathBuf,
+    /// Comma separated category thresholds, e.g. violence=0.5,self-harm=0.2
+    #[envconfig(from = "MODERATION_THRESHOLDS")]
+    pub moderation_thresholds: Option<String>,
+    /// Threshold of categories without own one, moderation API decision is used when not set
+    #[envconfig(from = "MODERATION_DEFAULT_THRESHOLD")]
+    pub moderation_default_threshold: Option<f32>,
+    /// Reject questions flagged by moderation in assistant APIs
+    #[envconfig(from = "MODERATION_SCREEN_QUESTIONS", default = "false")]
+    pub moderation_screen_questions: bool,
+    /// Comma separated checks of task answers: relevance, consistency, pii, language.
+    /// relevance and consistency apply only to tasks posting the answer with its question and context.
+    /// No checks by default, pii flags numeric answers looking like phone numbers
+    #[envconfig(from = "GUARDRAILS", default = "")]
+    pub guardrails: String,
+    /// One of: off, warn, enforce
+    #[envconfig(from = "GUARDRAIL_MODE", default = "warn")]
+    pub guardrail_mode: GuardrailMode,
+    /// Answers scored lower by any check fail it
+    #[envconfig(from = "GUARDRAIL_MIN_SCORE", default = "0.5")]
+    pub guardrail_min_score: f32,
+    /// Expected answer language for language check, one of: pl, en
+    #[envconfig(from = "GUARDRAIL_LANGUAGE")]
+    pub guardrail_language: Option<Language>,
+    /// Clue requests in tasks gathering hints, e.g. 'whoami', before giving up
+    #[envconfig(from = "EVIDENCE_MAX_ITERATIONS", default = "10")]
+    pub evidence_max_iterations: usize,
+    /// Confidence of candidate answer which stops gathering clues
+    #[envconfig(from = "EVIDENCE_CONFIDENCE_THRESHOLD", default = "0.8")]
+    pub evidence_confidence_threshold: f32,
+    /// Wait after duplicated or failed clue request, doubled on each next one
+    #[envconfig(from = "EVIDENCE_BACKOFF_MS", default = "500")]
+    pub evidence_backoff_ms: u64,
+    #[envconfig(from = "EVIDENCE_MAX_BACKOFF_MS", default = "8000")]
+    pub evidence_max_backoff_ms: u64,
+    /// JSON file with names, surnames, cities and occupations detected by anonymizer,
+    /// names are also used to recognize people in questions
+    #[envconfig(from = "ANONYMIZER_DICTIONARY", default = "anonymizer_dictionary.json")]
+    pub anonymizer_dictionary: PathBuf,
+    /// Detect personal data missing in the dictionary with LLM, which receives the raw text.
+    /// Not used when scrubbing prompts sent to LLM
+    #[envconfig(from = "ANONYMIZER_LLM", default = "false")]
+    pub anonymizer_llm: bool,
+    /// iCalendar file with events added in 'tools' REPL
+    #[envconfig(from = "CALENDAR_FILE", default = "calendar.ics")]
+    pub calendar_file: PathBuf,
+    /// One of: json, markdown
+    #[envconfig(from = "TODO_BACKEND", default = "markdown")]
+    pub todo_backend: TodoBackend,
+    #[envconfig(from = "TODO_FILE", default = "todo.md")]
+    pub todo_file: PathBuf,
+    /// One of: openai, local
+    #[envconfig(from = "TRANSCRIPTION_BACKEND", default = "openai")]
+    pub transcription_backend: TranscriptionBackendKind,
+    /// Audio is transcribed in chunks of at most this length
+    #[envconfig(from = "TRANSCRIPTION_CHUNK_SECS", default = "600")]
+    pub transcription_chunk_secs: u64,
+    #[envconfig(from = "WHISPER_CPP_BINARY", default = "whisper-cli")]
+    pub whisper_cpp_binary: String,
+    /// Path to whisper.cpp GGML model, e.g. ggml-base.bin
+    #[envconfig(from = "WHISPER_CPP_MODEL")]
+    pub whisper_cpp_model: Option<PathBuf>,
+    #[envconfig(from = "WHISPER_CPP_THREADS")]
+    pub whisper_cpp_threads: Option<usize>,
+    #[envconfig(from = "FETCH_MAX_RETRIES", default = "3")]
+    pub fetch_max_retries: u32,
+    #[envconfig(from = "FETCH_TIMEOUT_SECS", default = "30")]
+    pub fetch_timeout_secs: u64,
+    #[envconfig(from = "FETCH_HOST_INTERVAL_MS", default = "0")]
+    pub fetch_host_interval_ms: u64,
+    /// User agents separated with '|'
+    #[envconfig(from = "FETCH_USER_AGENTS")]
+    pub fetch_user_agents: Option<String>,
 }
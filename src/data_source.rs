@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::bail;
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+use strum_macros::{Display, EnumString};
+
+use crate::fetcher::Fetcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum DataSourceKind {
+    Http,
+    Fixtures,
+}
+
+/// Source of JSON documents returned by public data APIs.
+pub trait DataSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Gets JSON document. Returns `None` when the API does not have the requested data.
+    ///
+    /// * `url`: API endpoint URL
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Option<Value>>>;
+}
+
+/// Data downloaded from the APIs.
+pub struct HttpDataSource {
+    fetcher: Fetcher,
+}
+
+/// Data recorded in fixtures file, a JSON object with responses keyed by endpoint URL.
+/// Used for tests and offline runs.
+pub struct FixtureDataSource {
+    responses: HashMap<String, Value>,
+}
+
+impl HttpDataSource {
+    pub fn new(fetcher: Fetcher) -> Self {
+        Self { fetcher }
+    }
+
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<Value>> {
+        let response = self.fetcher.fetch(url).await?;
+        match response.status {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => bail!("GET {url} failed with status {status}"),
+            _ => response.json().map(Some),
+        }
+    }
+}
+
+impl DataSource for HttpDataSource {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Option<Value>>> {
+        self.fetch(url).boxed()
+    }
+}
+
+impl FixtureDataSource {
+    pub fn new(responses: HashMap<String, Value>) -> Self {
+        Self { responses }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let responses = serde_json::from_str(&content)?;
+        Ok(Self::new(responses))
+    }
+}
+
+impl DataSource for FixtureDataSource {
+    fn name(&self) -> &'static str {
+        "fixtures"
+    }
+
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Option<Value>>> {
+        log::debug!("Fixture lookup: {url}");
+        future::ready(Ok(self.responses.get(url).cloned())).boxed()
+    }
+}
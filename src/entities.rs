@@ -0,0 +1,299 @@
+use anyhow::anyhow;
+use async_openai::{config::OpenAIConfig, Client};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    anonymizer::{Dictionary, PiiKind},
+    config::Config,
+    utils,
+};
+
+const MODEL: &str = "gpt-3.5-turbo";
+
+const EXTRACT_CONTEXT: &str = r#"You are an extractor of people mentioned in Polish and English texts.
+Return names and surnames in their base (nominative) form, e.g. "Krystyna Ludek" for "Krystyny Ludek", surname is null when not mentioned.
+Return JSON object only: {"people": [{"name": "first name", "surname": "surname" or null}]}"#;
+
+/// Endings of inflected first names with their base form endings, longer endings first.
+/// Candidates are accepted only when found in the names dictionary.
+const NAME_RULES: &[(&str, &[&str])] = &[
+    ("dzie", &["da"]),
+    ("owi", &[""]),
+    ("iem", &[""]),
+    ("cie", &["ta"]),
+    ("rze", &["ra"]),
+    ("dze", &["ga"]),
+    ("em", &[""]),
+    ("ie", &["a", ""]),
+    ("ce", &["ka"]),
+    ("ii", &["ia"]),
+    ("ią", &["ia"]),
+    ("ię", &["ia"]),
+    ("a", &[""]),
+    ("y", &["a"]),
+    ("i", &["a", "ia"]),
+    ("ę", &["a"]),
+    ("ą", &["a"]),
+];
+
+/// Endings of inflected female surnames with their base form endings.
+const FEMALE_SURNAME_RULES: &[(&str, &str)] = &[
+    ("dzkiej", "dzka"),
+    ("skiej", "ska"),
+    ("ckiej", "cka"),
+    ("dzką", "dzka"),
+    ("ską", "ska"),
+    ("cką", "cka"),
+    ("ej", "a"),
+    ("ki", "ka"),
+    ("gi", "ga"),
+    ("y", "a"),
+    ("ą", "a"),
+    ("ę", "a"),
+];
+
+/// Endings of inflected male surnames with their base form endings.
+const MALE_SURNAME_RULES: &[(&str, &str)] = &[
+    ("dzkiego", "dzki"),
+    ("skiego", "ski"),
+    ("ckiego", "cki"),
+    ("dzkiemu", "dzki"),
+    ("skiemu", "ski"),
+    ("ckiemu", "cki"),
+    ("dzkim", "dzki"),
+    ("skim", "ski"),
+    ("ckim", "cki"),
+    ("ego", "y"),
+    ("emu", "y"),
+    ("owi", ""),
+    ("iem", ""),
+    ("em", ""),
+    ("a", ""),
+];
+
+/// Person mentioned in a text, with name and surname in base form.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PersonName {
+    pub name: String,
+    pub surname: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct People {
+    people: Vec<PersonName>,
+}
+
+/// Extracts people from texts. Known first names are recognized by lemmatizing rules,
+/// a capitalized word after the name is its surname. LLM is asked when rules find no one.
+pub struct EntityExtractor {
+    names: Dictionary,
+    words: Regex,
+    client: Option<Client<OpenAIConfig>>,
+}
+
+impl PersonName {
+    pub fn full_name(&self) -> String {
+        match &self.surname {
+            Some(surname) => format!("{} {surname}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl EntityExtractor {
+    /// * `names`: dictionary with known first names
+    /// * `client`: OpenAI client for LLM fallback, only rules are used when not provided
+    pub fn new(names: Dictionary, client: Option<Client<OpenAIConfig>>) -> anyhow::Result<Self> {
+        Ok(Self {
+            names,
+            words: Regex::new(r"\p{L}+")?,
+            client,
+        })
+    }
+
+    /// Creates extractor with names from the anonymizer dictionary and LLM fallback.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let names = match config.anonymizer_dictionary.exists() {
+            true => Dictionary::from_file(&config.anonymizer_dictionary)?,
+            false => Dictionary::default(),
+        };
+        Self::new(names, Some(Client::with_config(OpenAIConfig::default())))
+    }
+
+    /// Finds people by lemmatizing rules only.
+    ///
+    /// * `text`: text to search
+    pub fn find_people(&self, text: &str) -> Vec<PersonName> {
+        let words = self.words.find_iter(text).collect::<Vec<_>>();
+        let mut people = Vec::new();
+        let mut index = 0;
+        while index < words.len() {
+            let Some(name) = self.name_lemma(words[index].as_str()) else {
+                index += 1;
+                continue;
+            };
+
+            let surname = words
+                .get(index + 1)
+                .filter(|next| {
+                    is_capitalized(next.as_str())
+                        && text[words[index].end()..next.start()].trim().is_empty()
+                        && self.name_lemma(next.as_str()).is_none()
+                })
+                .map(|next| surname_lemma(next.as_str(), is_female(&name)));
+            index += if surname.is_some() { 2 } else { 1 };
+            people.push(PersonName { name, surname });
+        }
+        people
+    }
+
+    /// Finds people by lemmatizing rules, asking LLM when none is found.
+    ///
+    /// * `text`: text to search
+    pub async fn extract_people(&self, text: &str) -> anyhow::Result<Vec<PersonName>> {
+        let people = self.find_people(text);
+        let Some(client) = self.client.as_ref().filter(|_| people.is_empty()) else {
+            return Ok(people);
+        };
+
+        log::debug!("No known names found, asking LLM for people in '{text}'");
+        let answer = utils::ask_llm(client, MODEL, text, Some(EXTRACT_CONTEXT)).await?;
+        let people: People = utils::parse_json_answer(&answer)?;
+        Ok(people
+            .people
+            .into_iter()
+            .filter(|p| !p.name.trim().is_empty())
+            .collect())
+    }
+
+    /// Finds the first person mentioned in the text.
+    ///
+    /// * `text`: text to search
+    pub async fn extract_person(&self, text: &str) -> anyhow::Result<PersonName> {
+        self.extract_people(text)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("Can not find person in '{text}'"))
+    }
+
+    /// Base form of a capitalized word which is a known first name.
+    fn name_lemma(&self, word: &str) -> Option<String> {
+        if !is_capitalized(word) {
+            return None;
+        }
+
+        let mut candidates = vec![word.to_string()];
+        for (ending, replacements) in NAME_RULES {
+            let Some(stem) = word.strip_suffix(ending) else {
+                continue;
+            };
+            if stem.chars().count() < 2 {
+                continue;
+            }
+            for replacement in *replacements {
+                let candidate = format!("{stem}{replacement}");
+                candidates.extend(insert_vowel(&candidate));
+                candidates.push(candidate);
+            }
+        }
+
+        candidates
+            .into_iter()
+            .find(|candidate| self.names.contains(PiiKind::Name, candidate))
+    }
+}
+
+/// Base form of the surname, female surnames are inflected differently than male ones.
+fn surname_lemma(word: &str, female: bool) -> String {
+    let lemma = match female {
+        true => FEMALE_SURNAME_RULES.iter().find_map(|(ending, base)| {
+            word.strip_suffix(ending)
+                .filter(|stem| stem.chars().count() >= 2)
+                .map(|stem| format!("{stem}{base}"))
+        }),
+        false => MALE_SURNAME_RULES.iter().find_map(|(ending, base)| {
+            word.strip_suffix(ending)
+                .filter(|stem| stem.chars().count() >= 2)
+                .map(|stem| {
+                    let lemma = format!("{stem}{base}");
+                    // Mobile 'e', e.g. 'Ludka' -> 'Ludek'
+                    match base.is_empty() && lemma.ends_with('k') {
+                        true => insert_vowel(&lemma).unwrap_or(lemma),
+                        false => lemma,
+                    }
+                })
+        }),
+    };
+    lemma.unwrap_or_else(|| word.to_string())
+}
+
+/// Word with mobile 'e' restored before the final consonant, e.g. 'Mark' -> 'Marek', 'Pawł' -> 'Paweł'.
+fn insert_vowel(word: &str) -> Option<String> {
+    let chars = word.chars().collect::<Vec<_>>();
+    match chars.as_slice() {
+        [.., before, last] if !is_vowel(*before) && !is_vowel(*last) && before.is_lowercase() => {
+            let stem = chars[..chars.len() - 1].iter().collect::<String>();
+            Some(format!("{stem}e{last}"))
+        }
+        _ => None,
+    }
+}
+
+/// Polish female first names end with 'a', with rare exceptions like 'Kuba'.
+fn is_female(name: &str) -> bool {
+    name.ends_with('a') && !["Kuba", "Barnaba", "Bonawentura"].contains(&name)
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyąęó".contains(c.to_ascii_lowercase())
+}
+
+fn is_capitalized(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str, surname: Option<&str>) -> PersonName {
+        PersonName {
+            name: name.to_string(),
+            surname: surname.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_find_people_in_inflected_forms() {
+        let dictionary = Dictionary::from_file("anonymizer_dictionary.json").unwrap();
+        let extractor = EntityExtractor::new(dictionary, None).unwrap();
+
+        let cases = [
+            ("Co lubi Krystyny Ludek?", person("Krystyna", Some("Ludek"))),
+            ("Gdzie mieszka Tomasz Bzik?", person("Tomasz", Some("Bzik"))),
+            (
+                "Jaki kolor lubi Adama Kowalskiego brat?",
+                person("Adam", Some("Kowalski")),
+            ),
+            (
+                "Spotkałem się z Markiem Nowakiem.",
+                person("Marek", Some("Nowak")),
+            ),
+            (
+                "Opowiedz o Zofii Lewandowskiej",
+                person("Zofia", Some("Lewandowska")),
+            ),
+            ("Zadzwoń do Pawła Ludka", person("Paweł", Some("Ludek"))),
+            ("Daj znać Agnieszce.", person("Agnieszka", None)),
+            ("Tell me about John Smith", person("John", Some("Smith"))),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(extractor.find_people(text), vec![expected], "{text}");
+        }
+        assert!(extractor.find_people("Gdzie jest Polska?").is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_openai::{config::OpenAIConfig, Client};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{config::Config, utils};
+
+const CONTEXT: &str = r#"You are a detective answering the question using only the clues provided after ### marker and your base knowledge.
+Do not guess when the clues are not enough, lower the confidence instead.
+Return JSON object only: {"answer": "concise answer" or null when unknown, "confidence": number from 0 (no idea) to 1 (certain), "reasoning": "short justification"}"#;
+
+/// Source of clues, e.g. task endpoint returning a random hint on each call.
+pub trait ClueSource {
+    fn next_clue(&self) -> BoxFuture<'_, anyhow::Result<String>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct EvidenceOptions {
+    /// Clue requests made before giving up, including duplicates and failures
+    pub max_iterations: usize,
+    /// Candidates with lower confidence do not stop gathering
+    pub confidence_threshold: f32,
+    /// Wait after a duplicate or failed clue request, doubled up to `max_backoff`
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+/// Answer proposed by LLM after considering clues gathered so far.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Candidate {
+    pub answer: Option<String>,
+    pub confidence: f32,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conclusion {
+    /// The most confident candidate with an answer
+    pub candidate: Candidate,
+    pub clues: Vec<String>,
+    pub iterations: usize,
+    /// The candidate reached the confidence threshold
+    pub confident: bool,
+}
+
+/// Gathers clues and asks LLM for a candidate answer after each new one,
+/// until the candidate is confident enough or the iterations limit is reached.
+pub struct EvidenceEngine {
+    client: Client<OpenAIConfig>,
+    model: &'static str,
+    options: EvidenceOptions,
+}
+
+/// Distinct clues, compared without case and whitespace differences.
+#[derive(Debug, Default)]
+struct Clues {
+    clues: Vec<String>,
+    normalized: Vec<String>,
+}
+
+/// Exponential wait between clue requests which brought nothing new.
+#[derive(Debug)]
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Default for EvidenceOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 10,
+            confidence_threshold: 0.8,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl From<&Config> for EvidenceOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_iterations: config.evidence_max_iterations,
+            confidence_threshold: config.evidence_confidence_threshold,
+            initial_backoff: Duration::from_millis(config.evidence_backoff_ms),
+            max_backoff: Duration::from_millis(config.evidence_max_backoff_ms),
+        }
+    }
+}
+
+impl EvidenceEngine {
+    pub fn new(
+        client: Client<OpenAIConfig>,
+        model: &'static str,
+        options: EvidenceOptions,
+    ) -> Self {
+        Self {
+            client,
+            model,
+            options,
+        }
+    }
+
+    /// Creates engine with limits from configuration.
+    ///
+    /// * `config`: App configuration
+    /// * `model`: LLM model
+    pub fn from_config(config: &Config, model: &'static str) -> Self {
+        Self::new(
+            Client::with_config(OpenAIConfig::default()),
+            model,
+            EvidenceOptions::from(config),
+        )
+    }
+
+    /// Gathers clues until a confident answer to the question is found.
+    /// When the iterations limit is reached, the most confident candidate is returned
+    /// with `confident` not set. Fails only when no candidate proposed any answer.
+    ///
+    /// * `question`: question to answer
+    /// * `source`: source of clues
+    pub async fn gather(
+        &self,
+        question: &str,
+        source: &dyn ClueSource,
+    ) -> anyhow::Result<Conclusion> {
+        let mut clues = Clues::default();
+        let mut backoff = Backoff::new(self.options.initial_backoff, self.options.max_backoff);
+        let mut best: Option<Candidate> = None;
+
+        for iteration in 1..=self.options.max_iterations {
+            let clue = match source.next_clue().await {
+                Ok(clue) if clues.add(&clue) => clue,
+                Ok(_) => {
+                    log::info!("Duplicated clue in iteration {iteration}");
+                    backoff.wait().await;
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("Can not get clue in iteration {iteration}: {e}");
+                    backoff.wait().await;
+                    continue;
+                }
+            };
+            backoff.reset();
+            log::debug!("New clue: {clue}");
+
+            let candidate = self.evaluate(question, &clues).await?;
+            log::info!(
+                "Candidate after {} clues: {:?} with confidence {:.2}",
+                clues.len(),
+                candidate.answer,
+                candidate.confidence
+            );
+
+            if candidate.answer.is_none() {
+                continue;
+            }
+            if candidate.confidence >= self.options.confidence_threshold {
+                return Ok(Conclusion {
+                    candidate,
+                    clues: clues.clues,
+                    iterations: iteration,
+                    confident: true,
+                });
+            }
+            if best
+                .as_ref()
+                .is_none_or(|b| candidate.confidence > b.confidence)
+            {
+                best = Some(candidate);
+            }
+        }
+
+        let candidate = best.ok_or(anyhow!(
+            "No answer found after {} iterations",
+            self.options.max_iterations
+        ))?;
+        log::warn!(
+            "Confidence threshold not reached, using candidate with confidence {:.2}",
+            candidate.confidence
+        );
+        Ok(Conclusion {
+            candidate,
+            clues: clues.clues,
+            iterations: self.options.max_iterations,
+            confident: false,
+        })
+    }
+
+    async fn evaluate(&self, question: &str, clues: &Clues) -> anyhow::Result<Candidate> {
+        let context = format!("{CONTEXT}\n###\n{}", clues.prompt());
+        let answer = utils::ask_llm(&self.client, self.model, question, Some(&context)).await?;
+        let mut candidate: Candidate = utils::parse_json_answer(&answer)?;
+        candidate.confidence = candidate.confidence.clamp(0.0, 1.0);
+        candidate.answer = candidate
+            .answer
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        Ok(candidate)
+    }
+}
+
+impl Clues {
+    /// Adds the clue, returns false when it is a duplicate.
+    fn add(&mut self, clue: &str) -> bool {
+        let normalized = clue
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if normalized.is_empty() || self.normalized.contains(&normalized) {
+            return false;
+        }
+        self.normalized.push(normalized);
+        self.clues.push(clue.trim().to_string());
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.clues.len()
+    }
+
+    fn prompt(&self) -> String {
+        self.clues
+            .iter()
+            .enumerate()
+            .map(|(index, clue)| format!("{}. {clue}", index + 1))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    async fn wait(&mut self) {
+        if !self.current.is_zero() {
+            log::debug!("Waiting {} ms for next clue", self.current.as_millis());
+            sleep(self.current).await;
+        }
+        self.current = (self.current * 2).min(self.max);
+    }
+
+    fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clues_and_backoff() {
+        let mut clues = Clues::default();
+        assert!(clues.add("Urodził się w Krakowie."));
+        assert!(!clues.add("  urodził się   w krakowie. "));
+        assert!(!clues.add(""));
+        assert!(clues.add("Był programistą."));
+        assert_eq!(
+            clues.prompt(),
+            "1. Urodził się w Krakowie.\n2. Był programistą."
+        );
+
+        let mut backoff = Backoff::new(Duration::from_millis(1), Duration::from_millis(3));
+        backoff.wait().await;
+        assert_eq!(backoff.current, Duration::from_millis(2));
+        backoff.wait().await;
+        assert_eq!(backoff.current, Duration::from_millis(3));
+        backoff.reset();
+        assert_eq!(backoff.current, Duration::from_millis(1));
+    }
+}
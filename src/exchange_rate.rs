@@ -0,0 +1,480 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail};
+use chrono::{Duration, NaiveDate, Utc};
+use clap::{Args, ValueEnum};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::{
+    config::Config,
+    data_source::{DataSource, HttpDataSource},
+    fetcher::Fetcher,
+};
+
+const API_BASE_URL: &str = "https://api.nbp.pl/api/exchangerates/tables";
+/// Rates are not published on weekends and holidays, table B only once a week,
+/// so rate for a date is the last one published in this many days before it.
+const LOOKBACK_DAYS: i64 = 14;
+/// Longest date range accepted by NBP API in a single request.
+const MAX_RANGE_DAYS: i64 = 93;
+/// Conversion results are rounded to this many decimal places.
+const RESULT_DECIMAL_PLACES: u32 = 6;
+const BASE_CURRENCY: &str = "PLN";
+
+/// NBP exchange rate table.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumString, Deserialize, Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum NbpTable {
+    /// Average rates of most popular currencies, published every working day
+    A,
+    /// Average rates of other currencies, published weekly
+    B,
+    /// Buying and selling rates of most popular currencies
+    C,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RateKind {
+    /// Average rate, from table A or B
+    #[default]
+    Mid,
+    /// Buying rate, from table C
+    Bid,
+    /// Selling rate, from table C
+    Ask,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TableRate {
+    currency: String,
+    code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mid: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bid: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ask: Option<Decimal>,
+}
+
+/// Table as returned by NBP API.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateTable {
+    table: NbpTable,
+    no: String,
+    effective_date: NaiveDate,
+    rates: Vec<TableRate>,
+}
+
+/// Tables downloaded from NBP API, persisted between runs. Published tables never change,
+/// days without table are also remembered to avoid asking for them again.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RateCache {
+    /// Table or `None` when not published, keyed by `{table}/{date}`
+    days: BTreeMap<String, Option<RateTable>>,
+}
+
+/// Rate of a currency to PLN.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rate {
+    pub code: String,
+    pub currency: String,
+    pub table: NbpTable,
+    pub date: NaiveDate,
+    pub value: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conversion {
+    pub amount: Decimal,
+    pub from: String,
+    pub to: String,
+    /// Price of one unit of `from` currency in `to` currency
+    pub rate: Decimal,
+    pub result: Decimal,
+}
+
+/// Exchange rates from NBP (National Bank of Poland) with local cache of downloaded tables.
+pub struct ExchangeRates {
+    source: Arc<dyn DataSource>,
+    cache: Mutex<RateCache>,
+    cache_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct RateArgs {
+    /// Currency code, e.g. USD
+    pub code: String,
+
+    /// Rate date (YYYY-MM-DD), last published rate is used when not provided
+    #[arg(short, long, conflicts_with_all = ["start", "end"])]
+    pub date: Option<NaiveDate>,
+
+    /// First day of date range (YYYY-MM-DD)
+    #[arg(long, requires = "end")]
+    pub start: Option<NaiveDate>,
+
+    /// Last day of date range (YYYY-MM-DD)
+    #[arg(long, requires = "start")]
+    pub end: Option<NaiveDate>,
+
+    /// Rate kind
+    #[arg(short, long, value_enum, default_value_t = RateKind::Mid)]
+    pub kind: RateKind,
+
+    /// Convert the amount to the target currency
+    #[arg(short, long, conflicts_with_all = ["start", "end"])]
+    pub to: Option<String>,
+
+    /// Amount converted with `--to`
+    #[arg(short, long, default_value_t = Decimal::ONE, requires = "to")]
+    pub amount: Decimal,
+}
+
+impl RateKind {
+    fn tables(&self) -> &'static [NbpTable] {
+        match self {
+            Self::Mid => &[NbpTable::A, NbpTable::B],
+            Self::Bid | Self::Ask => &[NbpTable::C],
+        }
+    }
+}
+
+impl TableRate {
+    fn value(&self, kind: RateKind) -> Option<Decimal> {
+        match kind {
+            RateKind::Mid => self.mid,
+            RateKind::Bid => self.bid,
+            RateKind::Ask => self.ask,
+        }
+    }
+}
+
+impl RateTable {
+    fn rate(&self, code: &str, kind: RateKind) -> Option<Rate> {
+        let rate = self
+            .rates
+            .iter()
+            .find(|r| r.code.eq_ignore_ascii_case(code))?;
+        Some(Rate {
+            code: rate.code.clone(),
+            currency: rate.currency.clone(),
+            table: self.table,
+            date: self.effective_date,
+            value: rate.value(kind)?,
+        })
+    }
+}
+
+impl RateCache {
+    fn key(table: NbpTable, date: NaiveDate) -> String {
+        format!("{table}/{date}")
+    }
+}
+
+impl ExchangeRates {
+    /// Creates exchange rates client. Cache is loaded from the file when it exists.
+    ///
+    /// * `source`: NBP API data source
+    /// * `cache_path`: tables cache file, cache is kept only in memory when not provided
+    pub fn new(source: Arc<dyn DataSource>, cache_path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let cache = match &cache_path {
+            Some(path) if path.exists() => {
+                let content = std::fs::read_to_string(path)?;
+                serde_json::from_str(&content)
+                    .map_err(|e| anyhow!("Invalid rates cache {}: {e}", path.display()))?
+            }
+            _ => RateCache::default(),
+        };
+
+        Ok(Self {
+            source,
+            cache: Mutex::new(cache),
+            cache_path,
+        })
+    }
+
+    /// Creates exchange rates client using NBP API and cache file from configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let source = HttpDataSource::new(Fetcher::from_config(config)?);
+        Self::new(Arc::new(source), Some(config.exchange_rate_cache.clone()))
+    }
+
+    /// Gets rate of the currency to PLN published on the date or the last one before it.
+    ///
+    /// * `code`: currency code
+    /// * `date`: rate date, last published rate when not provided
+    /// * `kind`: average, buying or selling rate
+    pub async fn rate(
+        &self,
+        code: &str,
+        date: Option<NaiveDate>,
+        kind: RateKind,
+    ) -> anyhow::Result<Rate> {
+        if code.eq_ignore_ascii_case(BASE_CURRENCY) {
+            return Ok(Rate {
+                code: BASE_CURRENCY.to_string(),
+                currency: "złoty polski".to_string(),
+                table: NbpTable::A,
+                date: date.unwrap_or_else(|| Utc::now().date_naive()),
+                value: Decimal::ONE,
+            });
+        }
+
+        for table in kind.tables() {
+            let tables = match date {
+                Some(date) => {
+                    self.tables(*table, date - Duration::days(LOOKBACK_DAYS), date)
+                        .await?
+                }
+                None => self.latest_table(*table).await?.into_iter().collect(),
+            };
+            if let Some(rate) = tables.iter().rev().find_map(|t| t.rate(code, kind)) {
+                return Ok(rate);
+            }
+        }
+
+        bail!("Currency rate to PLN for {code} not found")
+    }
+
+    /// Gets rates of the currency to PLN published in the date range.
+    ///
+    /// * `code`: currency code
+    /// * `start`: first day of the range
+    /// * `end`: last day of the range
+    /// * `kind`: average, buying or selling rate
+    pub async fn rates_between(
+        &self,
+        code: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        kind: RateKind,
+    ) -> anyhow::Result<Vec<Rate>> {
+        if start > end {
+            bail!("Range start {start} is after its end {end}");
+        }
+
+        for table in kind.tables() {
+            let rates = self
+                .tables(*table, start, end)
+                .await?
+                .iter()
+                .filter_map(|t| t.rate(code, kind))
+                .collect::<Vec<_>>();
+            if !rates.is_empty() {
+                return Ok(rates);
+            }
+        }
+
+        bail!("Currency rates to PLN for {code} between {start} and {end} not found")
+    }
+
+    /// Converts amount between currencies with average rates, through PLN.
+    ///
+    /// * `amount`: amount in `from` currency
+    /// * `from`: source currency code
+    /// * `to`: target currency code
+    /// * `date`: rates date, last published rates when not provided
+    pub async fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        date: Option<NaiveDate>,
+    ) -> anyhow::Result<Conversion> {
+        let from_rate = self.rate(from, date, RateKind::Mid).await?;
+        let to_rate = self.rate(to, date, RateKind::Mid).await?;
+
+        let rate = from_rate
+            .value
+            .checked_div(to_rate.value)
+            .ok_or(anyhow!("Can not convert {from} to {to}, rate is zero"))?;
+        let result = amount
+            .checked_mul(rate)
+            .ok_or(anyhow!("Conversion of {amount} {from} overflows"))?
+            .round_dp(RESULT_DECIMAL_PLACES);
+
+        Ok(Conversion {
+            amount,
+            from: from_rate.code,
+            to: to_rate.code,
+            rate: rate.round_dp(RESULT_DECIMAL_PLACES),
+            result,
+        })
+    }
+
+    /// Tables published in the date range, oldest first. Missing days are downloaded and cached.
+    async fn tables(
+        &self,
+        table: NbpTable,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> anyhow::Result<Vec<RateTable>> {
+        let missing = {
+            let cache = self.cache.lock().unwrap();
+            start
+                .iter_days()
+                .take_while(|d| *d <= end)
+                .any(|d| !cache.days.contains_key(&RateCache::key(table, d)))
+        };
+
+        if missing {
+            let mut window_start = start;
+            while window_start <= end {
+                let window_end = end.min(window_start + Duration::days(MAX_RANGE_DAYS - 1));
+                let url = format!("{API_BASE_URL}/{table}/{window_start}/{window_end}/");
+                let tables = self.fetch_tables(&url).await?;
+                self.store(table, window_start, window_end, tables)?;
+                window_start = window_end + Duration::days(1);
+            }
+        }
+
+        let cache = self.cache.lock().unwrap();
+        let tables = start
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .filter_map(|d| cache.days.get(&RateCache::key(table, d)).cloned().flatten())
+            .collect();
+
+        Ok(tables)
+    }
+
+    /// Last published table, always downloaded.
+    async fn latest_table(&self, table: NbpTable) -> anyhow::Result<Option<RateTable>> {
+        let url = format!("{API_BASE_URL}/{table}/");
+        let latest = self.fetch_tables(&url).await?.pop();
+
+        if let Some(latest) = &latest {
+            let date = latest.effective_date;
+            self.store(table, date, date, vec![latest.clone()])?;
+        }
+
+        Ok(latest)
+    }
+
+    async fn fetch_tables(&self, url: &str) -> anyhow::Result<Vec<RateTable>> {
+        log::debug!("Downloading NBP tables: {url}");
+        match self.source.get(url).await? {
+            Some(response) => Ok(serde_json::from_value(response)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Caches downloaded tables. Days before today without table are cached as not published,
+    /// today's table may be published later.
+    fn store(
+        &self,
+        table: NbpTable,
+        start: NaiveDate,
+        end: NaiveDate,
+        tables: Vec<RateTable>,
+    ) -> anyhow::Result<()> {
+        let today = Utc::now().date_naive();
+        let mut cache = self.cache.lock().unwrap();
+
+        for day in start.iter_days().take_while(|d| *d <= end && *d < today) {
+            cache.days.entry(RateCache::key(table, day)).or_insert(None);
+        }
+        for rate_table in tables {
+            let key = RateCache::key(table, rate_table.effective_date);
+            cache.days.insert(key, Some(rate_table));
+        }
+
+        match &self.cache_path {
+            Some(path) => save_cache(path, &cache),
+            None => Ok(()),
+        }
+    }
+}
+
+fn save_cache(path: &Path, cache: &RateCache) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_string(cache)?)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Prints currency rate, rates in date range or currency conversion.
+///
+/// * `config`: App configuration
+/// * `args`: rate command arguments
+pub async fn run(config: &Config, args: RateArgs) -> anyhow::Result<()> {
+    let rates = ExchangeRates::from_config(config)?;
+
+    if let Some(to) = &args.to {
+        let conversion = rates
+            .convert(args.amount, &args.code, to, args.date)
+            .await?;
+        println!(
+            "{} {} = {} {} (rate {})",
+            conversion.amount, conversion.from, conversion.result, conversion.to, conversion.rate
+        );
+        return Ok(());
+    }
+
+    let list = match (args.start, args.end) {
+        (Some(start), Some(end)) => {
+            rates
+                .rates_between(&args.code, start, end, args.kind)
+                .await?
+        }
+        _ => vec![rates.rate(&args.code, args.date, args.kind).await?],
+    };
+    for rate in list {
+        println!(
+            "{}\t{}\t{} PLN\t(table {}, {})",
+            rate.date, rate.code, rate.value, rate.table, rate.currency
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data_source::FixtureDataSource;
+
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rates_and_conversion_from_fixtures() {
+        let source = FixtureDataSource::from_file("fixtures/knowledge.json").unwrap();
+        let rates = ExchangeRates::new(Arc::new(source), None).unwrap();
+
+        // Saturday, rates from Friday are used
+        let saturday = Some(date("2024-01-06"));
+        let usd = rates.rate("usd", saturday, RateKind::Mid).await.unwrap();
+        assert_eq!(usd.date, date("2024-01-05"));
+        assert_eq!(usd.value, Decimal::new(39909, 4));
+
+        let conversion = rates
+            .convert(Decimal::from(100), "USD", "EUR", saturday)
+            .await
+            .unwrap();
+        assert_eq!(conversion.rate, Decimal::new(913919, 6));
+        assert_eq!(conversion.result, Decimal::new(91391866, 6));
+
+        let range = rates
+            .rates_between("USD", date("2024-01-02"), date("2024-01-04"), RateKind::Mid)
+            .await
+            .unwrap();
+        assert_eq!(range.len(), 3);
+        assert_eq!(range[0].date, date("2024-01-02"));
+    }
+}
@@ -0,0 +1,380 @@
+use anyhow::{anyhow, bail};
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements which never contain main content of the page.
+const SKIPPED_ELEMENTS: [&str; 13] = [
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "form", "button", "nav",
+    "aside", "footer", "head",
+];
+
+/// Class or id fragments which suggest that element contains article content.
+const POSITIVE_HINTS: [&str; 8] = [
+    "article", "content", "main", "post", "entry", "text", "story", "body",
+];
+
+/// Class or id fragments which suggest that element is page boilerplate.
+const NEGATIVE_HINTS: [&str; 14] = [
+    "comment", "sidebar", "footer", "header", "nav", "menu", "banner", "cookie", "share", "social",
+    "related", "promo", "advert", "popup",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentKind {
+    Html,
+    Pdf,
+    PlainText,
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtractedDocument {
+    pub kind: ContentKind,
+    pub title: Option<String>,
+    pub text: String,
+}
+
+/// Detects kind of downloaded content using `Content-Type` header value and body signature.
+///
+/// * `content_type`: value of `Content-Type` response header
+/// * `body`: raw response body
+pub fn detect_content_kind(content_type: Option<&str>, body: &[u8]) -> ContentKind {
+    if body.starts_with(b"%PDF-") {
+        return ContentKind::Pdf;
+    }
+
+    let mime = content_type
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_lowercase());
+
+    match mime.as_deref() {
+        Some("text/html") | Some("application/xhtml+xml") => ContentKind::Html,
+        Some("application/pdf") => ContentKind::Pdf,
+        Some(m) if m.starts_with("text/") => ContentKind::PlainText,
+        Some("application/json") => ContentKind::PlainText,
+        Some(other) if !looks_like_html(body) => ContentKind::Other(other.to_string()),
+        _ if looks_like_html(body) => ContentKind::Html,
+        _ => ContentKind::PlainText,
+    }
+}
+
+/// Converts downloaded content to text which can be passed to LLM.
+/// HTML pages are reduced to main content and converted to Markdown.
+///
+/// * `content_type`: value of `Content-Type` response header
+/// * `body`: raw response body
+pub fn extract(content_type: Option<&str>, body: &[u8]) -> anyhow::Result<ExtractedDocument> {
+    let kind = detect_content_kind(content_type, body);
+    log::debug!("Detected content kind: {kind:?}");
+
+    match kind {
+        ContentKind::Html => Ok(html_to_markdown(&String::from_utf8_lossy(body))),
+        ContentKind::Pdf => Ok(ExtractedDocument {
+            kind,
+            title: None,
+            text: pdf_to_text(body)?,
+        }),
+        ContentKind::PlainText => Ok(ExtractedDocument {
+            kind,
+            title: None,
+            text: String::from_utf8_lossy(body).into_owned(),
+        }),
+        ContentKind::Other(mime) => bail!("Unsupported content type: {mime}"),
+    }
+}
+
+/// Finds main content of the HTML page (readability-style heuristic) and converts it to Markdown.
+///
+/// * `html`: HTML document
+pub fn html_to_markdown(html: &str) -> ExtractedDocument {
+    let document = Html::parse_document(html);
+
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|s| document.select(&s).next())
+        .map(|t| collapse_whitespace(&t.text().collect::<String>()))
+        .filter(|t| !t.is_empty());
+
+    let root = find_main_content(&document).unwrap_or_else(|| document.root_element());
+
+    let mut markdown = String::new();
+    render_markdown(root, &mut markdown);
+
+    ExtractedDocument {
+        kind: ContentKind::Html,
+        title,
+        text: normalize_blank_lines(&markdown),
+    }
+}
+
+/// Extracts text layer from PDF document.
+///
+/// * `bytes`: PDF file content
+pub fn pdf_to_text(bytes: &[u8]) -> anyhow::Result<String> {
+    let text = pdf_extract::extract_text_from_mem(bytes)
+        .map_err(|e| anyhow!("PDF text extraction failed: {e}"))?;
+    Ok(normalize_blank_lines(&text))
+}
+
+fn looks_like_html(body: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&body[..body.len().min(512)]).to_lowercase();
+    let head = head.trim_start();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.contains("<body")
+}
+
+fn find_main_content(document: &Html) -> Option<ElementRef<'_>> {
+    let selector = Selector::parse("article, main, section, div, td").ok()?;
+
+    document
+        .select(&selector)
+        .filter(|e| !is_skipped(e))
+        .map(|e| (content_score(&e), e))
+        .filter(|(score, _)| *score > 0.0)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, e)| e)
+}
+
+fn content_score(element: &ElementRef) -> f64 {
+    let Ok(paragraphs) = Selector::parse("p, pre, blockquote, li") else {
+        return 0.0;
+    };
+
+    // Only direct content counts, so parents do not win just by wrapping the best candidate.
+    let mut score = element
+        .select(&paragraphs)
+        .filter(|p| p.parent().and_then(ElementRef::wrap) == Some(*element))
+        .map(|p| {
+            let text = p.text().collect::<String>();
+            let length = text.trim().chars().count() as f64;
+            let commas = text.matches(',').count() as f64;
+            (length / 100.0).min(3.0) + commas + 1.0
+        })
+        .sum::<f64>();
+
+    let hints = [element.value().attr("class"), element.value().attr("id")]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if POSITIVE_HINTS.iter().any(|h| hints.contains(h)) {
+        score += 25.0;
+    }
+    if NEGATIVE_HINTS.iter().any(|h| hints.contains(h)) {
+        score -= 25.0;
+    }
+    if matches!(element.value().name(), "article" | "main") {
+        score += 10.0;
+    }
+
+    score * (1.0 - link_density(element))
+}
+
+fn link_density(element: &ElementRef) -> f64 {
+    let text_length = element.text().map(|t| t.trim().len()).sum::<usize>();
+    if text_length == 0 {
+        return 1.0;
+    }
+
+    let Ok(links) = Selector::parse("a") else {
+        return 0.0;
+    };
+    let links_length = element
+        .select(&links)
+        .flat_map(|a| a.text())
+        .map(|t| t.trim().len())
+        .sum::<usize>();
+
+    links_length as f64 / text_length as f64
+}
+
+fn is_skipped(element: &ElementRef) -> bool {
+    let name = element.value().name();
+    if SKIPPED_ELEMENTS.contains(&name) {
+        return true;
+    }
+    element.value().attr("hidden").is_some() || element.value().attr("aria-hidden") == Some("true")
+}
+
+fn render_markdown(element: ElementRef, output: &mut String) {
+    if is_skipped(&element) {
+        return;
+    }
+
+    let name = element.value().name();
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            let text = inline_text(element);
+            if !text.is_empty() {
+                output.push_str(&format!("\n\n{} {text}\n\n", "#".repeat(level)));
+            }
+        }
+        "p" | "div" | "section" | "article" | "main" | "header" | "figure" | "table" => {
+            output.push_str("\n\n");
+            render_children(element, output);
+            output.push_str("\n\n");
+        }
+        "tr" => {
+            let cells = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .map(inline_text)
+                .collect::<Vec<_>>();
+            output.push_str(&format!("\n| {} |", cells.join(" | ")));
+        }
+        "li" => {
+            output.push_str("\n- ");
+            render_children(element, output);
+        }
+        "ul" | "ol" => {
+            output.push('\n');
+            render_children(element, output);
+            output.push('\n');
+        }
+        "blockquote" => {
+            let text = inline_text(element);
+            output.push_str(&format!("\n\n> {text}\n\n"));
+        }
+        "pre" => {
+            let code = element.text().collect::<String>();
+            output.push_str(&format!("\n\n```\n{}\n```\n\n", code.trim_end()));
+        }
+        "code" => output.push_str(&format!("`{}`", element.text().collect::<String>())),
+        "br" => output.push('\n'),
+        "hr" => output.push_str("\n\n---\n\n"),
+        "strong" | "b" => output.push_str(&format!("**{}**", inline_text(element))),
+        "em" | "i" => output.push_str(&format!("_{}_", inline_text(element))),
+        "a" => {
+            let text = inline_text(element);
+            match element.value().attr("href") {
+                Some(href) if !text.is_empty() && !href.starts_with('#') => {
+                    output.push_str(&format!("[{text}]({href})"))
+                }
+                _ => output.push_str(&text),
+            }
+        }
+        "img" => {
+            if let Some(alt) = element.value().attr("alt").filter(|a| !a.is_empty()) {
+                output.push_str(&format!("![{alt}]"));
+            }
+        }
+        _ => render_children(element, output),
+    }
+}
+
+fn render_children(element: ElementRef, output: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let text = collapse_whitespace(text);
+                if text.is_empty() {
+                    continue;
+                }
+                if !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                    output.push(' ');
+                }
+                output.push_str(&text);
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    render_markdown(child, output);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn inline_text(element: ElementRef) -> String {
+    collapse_whitespace(&element.text().collect::<Vec<_>>().join(" "))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn normalize_blank_lines(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_content_kind() {
+        assert_eq!(
+            detect_content_kind(Some("text/html; charset=utf-8"), b"<p>"),
+            ContentKind::Html
+        );
+        assert_eq!(
+            detect_content_kind(Some("application/octet-stream"), b"%PDF-1.7"),
+            ContentKind::Pdf
+        );
+        assert_eq!(
+            detect_content_kind(Some("text/plain"), b"plain"),
+            ContentKind::PlainText
+        );
+        assert_eq!(
+            detect_content_kind(None, b"<!DOCTYPE html><html></html>"),
+            ContentKind::Html
+        );
+        assert_eq!(
+            detect_content_kind(Some("image/png"), b"\x89PNG"),
+            ContentKind::Other("image/png".into())
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown_main_content() {
+        let html = r#"
+            <html>
+            <head><title> Pizza  history </title><style>p { color: red }</style></head>
+            <body>
+                <nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+                <div class="sidebar"><p>Subscribe, share, like, comment.</p></div>
+                <div class="post-content">
+                    <h2>Margherita</h2>
+                    <p>Pizza Margherita was named after queen Margherita of Savoy, in 1889.</p>
+                    <p>It uses tomatoes, mozzarella and <a href="https://basil.example">basil</a>.</p>
+                </div>
+                <footer><p>Copyright, all rights reserved.</p></footer>
+            </body>
+            </html>
+        "#;
+
+        let document = html_to_markdown(html);
+        assert_eq!(document.title.as_deref(), Some("Pizza history"));
+        assert!(document.text.starts_with("## Margherita"));
+        assert!(document.text.contains("queen Margherita of Savoy"));
+        assert!(document.text.contains("[basil](https://basil.example)"));
+        assert!(!document.text.contains("Subscribe"));
+        assert!(!document.text.contains("Copyright"));
+        assert!(!document.text.contains("Home"));
+    }
+
+    #[test]
+    fn test_extract_pdf_text() {
+        let body = std::fs::read("fixtures/sample.pdf").unwrap();
+        let document = extract(Some("application/pdf"), &body).unwrap();
+        assert_eq!(document.kind, ContentKind::Pdf);
+        assert_eq!(document.title, None);
+        assert_eq!(document.text, "Rysy are the highest peak of Poland.");
+
+        assert!(pdf_to_text(b"%PDF-1.4 broken").is_err());
+    }
+}
@@ -0,0 +1,486 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use reqwest::{
+    header::{self, HeaderMap},
+    multipart::Form,
+    IntoUrl, Response, StatusCode,
+};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{sync::Mutex, time::sleep};
+use url::Url;
+
+use crate::{
+    config::Config,
+    extract::{self, ExtractedDocument},
+};
+
+const DEFAULT_USER_AGENTS: [&str; 4] = [
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:124.0) Gecko/20100101 Firefox/124.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+    "curl/8.6.0",
+];
+
+/// Time slots of the last requests per host, shared by all fetchers,
+/// so the host interval is kept also between fetchers created by different tasks and modules.
+static HOST_SLOTS: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(Default::default);
+
+/// Decides if received response is a block page (captcha, bot detection etc.) instead of requested content.
+pub trait BlockDetector: Send + Sync {
+    fn name(&self) -> &str;
+    fn is_blocked(&self, status: StatusCode, body: &str) -> bool;
+}
+
+/// Detects block pages by phrases which they contain.
+pub struct KeywordBlockDetector {
+    keywords: Vec<String>,
+}
+
+/// Detects block pages by response status codes.
+pub struct StatusBlockDetector {
+    statuses: Vec<StatusCode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetcherOptions {
+    pub max_retries: u32,
+    pub timeout: Duration,
+    pub host_interval: Duration,
+    pub user_agents: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct FetchedResponse {
+    pub url: Url,
+    pub status: StatusCode,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// HTTP client shared by tasks. Retries transient errors, limits request rate per host,
+/// rotates user agents and skips responses recognized as block pages.
+#[derive(Clone)]
+pub struct Fetcher {
+    client: ClientWithMiddleware,
+    plain_client: reqwest::Client,
+    options: Arc<FetcherOptions>,
+    detectors: Vec<Arc<dyn BlockDetector>>,
+}
+
+impl KeywordBlockDetector {
+    pub fn new<S: Into<String>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            keywords: keywords
+                .into_iter()
+                .map(|k| k.into().to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl BlockDetector for KeywordBlockDetector {
+    fn name(&self) -> &str {
+        "keyword"
+    }
+
+    fn is_blocked(&self, _status: StatusCode, body: &str) -> bool {
+        let body = body.to_lowercase();
+        self.keywords.iter().any(|k| body.contains(k))
+    }
+}
+
+impl StatusBlockDetector {
+    pub fn new(statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        Self {
+            statuses: statuses.into_iter().collect(),
+        }
+    }
+}
+
+impl BlockDetector for StatusBlockDetector {
+    fn name(&self) -> &str {
+        "status"
+    }
+
+    fn is_blocked(&self, status: StatusCode, _body: &str) -> bool {
+        self.statuses.contains(&status)
+    }
+}
+
+impl Default for FetcherOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            timeout: Duration::from_secs(30),
+            host_interval: Duration::ZERO,
+            user_agents: DEFAULT_USER_AGENTS.map(String::from).to_vec(),
+        }
+    }
+}
+
+impl From<&Config> for FetcherOptions {
+    fn from(config: &Config) -> Self {
+        let user_agents = config
+            .fetch_user_agents
+            .as_ref()
+            .map(|u| {
+                u.split('|')
+                    .map(|a| a.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| DEFAULT_USER_AGENTS.map(String::from).to_vec());
+
+        Self {
+            max_retries: config.fetch_max_retries,
+            timeout: Duration::from_secs(config.fetch_timeout_secs),
+            host_interval: Duration::from_millis(config.fetch_host_interval_ms),
+            user_agents,
+        }
+    }
+}
+
+impl FetchedResponse {
+    async fn read(response: reqwest::Response) -> anyhow::Result<Self> {
+        let url = response.url().clone();
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|c| c.to_str().ok())
+            .map(String::from);
+        let body = response.bytes().await?.to_vec();
+
+        Ok(Self {
+            url,
+            status,
+            content_type,
+            body,
+        })
+    }
+
+    /// Fails on error status, e.g. not found page, instead of treating its body as content.
+    pub fn error_for_status(self) -> anyhow::Result<Self> {
+        if !self.status.is_success() {
+            bail!("GET {} failed with status {}", self.url, self.status);
+        }
+        Ok(self)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Converts response body to text using content extraction (HTML main content, PDF text layer).
+    pub fn document(&self) -> anyhow::Result<ExtractedDocument> {
+        extract::extract(self.content_type.as_deref(), &self.body)
+    }
+}
+
+impl Fetcher {
+    pub fn new(options: FetcherOptions) -> anyhow::Result<Self> {
+        let plain_client = reqwest::Client::builder()
+            .timeout(options.timeout)
+            .gzip(true)
+            .build()?;
+
+        let retry_policy =
+            ExponentialBackoff::builder().build_with_max_retries(options.max_retries);
+        let client = ClientBuilder::new(plain_client.clone())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        Ok(Self {
+            client,
+            plain_client,
+            options: Arc::new(options),
+            detectors: Vec::new(),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        Self::new(config.into())
+    }
+
+    /// Adds block page detector. Blocked responses are retried with the next user agent from the pool.
+    pub fn with_detector(mut self, detector: impl BlockDetector + 'static) -> Self {
+        self.detectors.push(Arc::new(detector));
+        self
+    }
+
+    /// Downloads the URL, failing on error status.
+    pub async fn get(&self, url: impl IntoUrl) -> anyhow::Result<FetchedResponse> {
+        self.fetch(url).await?.error_for_status()
+    }
+
+    /// Downloads the URL, responses with any status are returned, except block pages.
+    pub async fn fetch(&self, url: impl IntoUrl) -> anyhow::Result<FetchedResponse> {
+        let url = url.into_url()?;
+
+        for user_agent in self.options.user_agents.iter() {
+            self.wait_for_host_slot(&url).await;
+            log::debug!("GET {url} with user agent '{user_agent}'");
+
+            let response = self
+                .client
+                .get(url.clone())
+                .header(header::USER_AGENT, user_agent)
+                .send()
+                .await;
+            let response = match response {
+                Ok(r) => r,
+                Err(err) => {
+                    log::error!("Request to {url} failed: {err}");
+                    continue;
+                }
+            };
+
+            let fetched = FetchedResponse::read(response).await?;
+            if let Some(detector) = self.blocked_by(&fetched) {
+                log::debug!(
+                    "Response from {url} recognized as block page by '{}' detector, trying next user agent.",
+                    detector.name()
+                );
+                continue;
+            }
+
+            return Ok(fetched);
+        }
+
+        Err(anyhow!("Fetching {url} failed"))
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: impl IntoUrl) -> anyhow::Result<T> {
+        self.get(url).await?.json()
+    }
+
+    pub async fn get_document(&self, url: impl IntoUrl) -> anyhow::Result<ExtractedDocument> {
+        self.get(url).await?.document()
+    }
+
+    /// POST is not idempotent, e.g. a timed out answer may have been accepted,
+    /// so this request is sent without retries.
+    pub async fn post_json<P: Serialize, T: DeserializeOwned>(
+        &self,
+        url: impl IntoUrl,
+        payload: &P,
+    ) -> anyhow::Result<T> {
+        let url = url.into_url()?;
+        self.wait_for_host_slot(&url).await;
+        log::debug!("POST {url}");
+
+        let response = self
+            .plain_client
+            .post(url)
+            .headers(self.default_headers())
+            .json(payload)
+            .send()
+            .await?;
+
+        Ok(Self::ensure_success(response).await?.json::<T>().await?)
+    }
+
+    /// Multipart body can not be cloned, so this request is sent without retries.
+    pub async fn post_multipart<T: DeserializeOwned>(
+        &self,
+        url: impl IntoUrl,
+        form: Form,
+    ) -> anyhow::Result<T> {
+        self.post_multipart_with_headers(url, HeaderMap::new(), form)
+            .await
+    }
+
+    /// Sends multipart request with additional headers, e.g. API authorization.
+    ///
+    /// * `url`: request URL
+    /// * `headers`: headers added to the default ones
+    /// * `form`: multipart body
+    pub async fn post_multipart_with_headers<T: DeserializeOwned>(
+        &self,
+        url: impl IntoUrl,
+        headers: HeaderMap,
+        form: Form,
+    ) -> anyhow::Result<T> {
+        let url = url.into_url()?;
+        self.wait_for_host_slot(&url).await;
+        log::debug!("POST multipart {url}");
+
+        let response = self
+            .plain_client
+            .post(url)
+            .headers(self.default_headers())
+            .headers(headers)
+            .multipart(form)
+            .send()
+            .await?;
+
+        Ok(Self::ensure_success(response).await?.json::<T>().await?)
+    }
+
+    /// Fails on error status with URL, status and response body, instead of a confusing
+    /// deserialization error of the error body.
+    async fn ensure_success(response: Response) -> anyhow::Result<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let url = response.url().clone();
+        let body = response.text().await.unwrap_or_default();
+        match body.trim() {
+            "" => bail!("POST {url} failed with status {status}"),
+            body => bail!("POST {url} failed with status {status}: {body}"),
+        }
+    }
+
+    fn default_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(user_agent) = self
+            .options
+            .user_agents
+            .first()
+            .and_then(|u| u.parse().ok())
+        {
+            headers.insert(header::USER_AGENT, user_agent);
+        }
+        headers
+    }
+
+    fn blocked_by(&self, response: &FetchedResponse) -> Option<&dyn BlockDetector> {
+        if self.detectors.is_empty() {
+            return None;
+        }
+
+        let body = response.text();
+        self.detectors
+            .iter()
+            .find(|d| d.is_blocked(response.status, &body))
+            .map(|d| d.as_ref())
+    }
+
+    async fn wait_for_host_slot(&self, url: &Url) {
+        if self.options.host_interval.is_zero() {
+            return;
+        }
+        let Some(host) = url.host_str() else {
+            return;
+        };
+
+        let now = Instant::now();
+        let slot = {
+            let mut host_slots = HOST_SLOTS.lock().await;
+            let slot = host_slots
+                .get(host)
+                .map(|last| (*last + self.options.host_interval).max(now))
+                .unwrap_or(now);
+            host_slots.insert(host.to_string(), slot);
+            slot
+        };
+
+        let wait = slot.saturating_duration_since(now);
+        if !wait.is_zero() {
+            log::debug!("Rate limit for {host}, waiting {} ms", wait.as_millis());
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use envconfig::Envconfig;
+
+    use super::*;
+
+    #[test]
+    fn test_block_detectors() {
+        let keywords = KeywordBlockDetector::new(["captcha", "Access Denied"]);
+        assert!(keywords.is_blocked(StatusCode::OK, "<h1>ACCESS DENIED</h1>"));
+        assert!(keywords.is_blocked(StatusCode::OK, "Solve the CAPTCHA to continue"));
+        assert!(!keywords.is_blocked(StatusCode::OK, "<p>Article content</p>"));
+
+        let statuses =
+            StatusBlockDetector::new([StatusCode::FORBIDDEN, StatusCode::TOO_MANY_REQUESTS]);
+        assert!(statuses.is_blocked(StatusCode::TOO_MANY_REQUESTS, ""));
+        assert!(!statuses.is_blocked(StatusCode::OK, "captcha"));
+    }
+
+    #[test]
+    fn test_error_for_status() {
+        let response = |status| FetchedResponse {
+            url: Url::parse("https://example.com/missing").unwrap(),
+            status,
+            content_type: Some("text/html".to_string()),
+            body: b"<h1>Not Found</h1>".to_vec(),
+        };
+
+        assert!(response(StatusCode::OK).error_for_status().is_ok());
+        let error = response(StatusCode::NOT_FOUND)
+            .error_for_status()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GET https://example.com/missing failed with status 404 Not Found"
+        );
+    }
+
+    #[test]
+    fn test_options_from_config() {
+        let env = |user_agents: Option<&str>| {
+            let mut env = HashMap::from([
+                (
+                    "AI_DEVS2_API_URL".to_string(),
+                    "http://localhost".to_string(),
+                ),
+                ("AI_DEVS2_API_KEY".to_string(), "key".to_string()),
+                ("FETCH_HOST_INTERVAL_MS".to_string(), "250".to_string()),
+            ]);
+            if let Some(user_agents) = user_agents {
+                env.insert("FETCH_USER_AGENTS".to_string(), user_agents.to_string());
+            }
+            FetcherOptions::from(&Config::init_from_hashmap(&env).unwrap())
+        };
+
+        let options = env(Some(" agent/1 | | agent/2 "));
+        assert_eq!(options.user_agents, vec!["agent/1", "agent/2"]);
+        assert_eq!(options.host_interval, Duration::from_millis(250));
+        assert_eq!(env(Some(" | ")).user_agents, DEFAULT_USER_AGENTS.to_vec());
+        assert_eq!(env(None).user_agents, DEFAULT_USER_AGENTS.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_host_slots_spacing() {
+        let interval = Duration::from_millis(50);
+        let options = FetcherOptions {
+            host_interval: interval,
+            ..Default::default()
+        };
+        // Separate fetchers share the slots, as fetchers created by different tasks do
+        let first = Fetcher::new(options.clone()).unwrap();
+        let second = Fetcher::new(options).unwrap();
+        let url = Url::parse("http://host-slots.test/page").unwrap();
+        let other = Url::parse("http://other-host-slots.test/page").unwrap();
+
+        let started = Instant::now();
+        first.wait_for_host_slot(&url).await;
+        second.wait_for_host_slot(&other).await;
+        assert!(started.elapsed() < interval);
+
+        second.wait_for_host_slot(&url).await;
+        first.wait_for_host_slot(&url).await;
+        assert!(started.elapsed() >= interval * 2);
+    }
+}
@@ -0,0 +1,284 @@
+mod language;
+mod llm;
+mod pii;
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use async_openai::{config::OpenAIConfig, Client};
+use futures::future::{join_all, BoxFuture};
+use serde_json::Value;
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+pub use language::{Language, LanguageCheck};
+pub use llm::{ConsistencyCheck, RelevanceCheck};
+use pii::PiiCheck;
+
+/// Model used by checks verifying answers with LLM.
+const CHECK_MODEL: &str = "gpt-3.5-turbo";
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum GuardrailKind {
+    /// Answer addresses the question
+    Relevance,
+    /// Answer is consistent with the context
+    Consistency,
+    /// Answer does not leak personal data
+    Pii,
+    /// Answer is written in the expected language
+    Language,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum GuardrailMode {
+    /// Checks are not run
+    Off,
+    /// Failed checks are logged
+    Warn,
+    /// Failed checks reject the answer
+    Enforce,
+}
+
+/// Answer with the question and context it was generated from, if known.
+#[derive(Debug, Clone, Copy)]
+pub struct GuardInput<'a> {
+    pub question: Option<&'a str>,
+    pub answer: &'a str,
+    pub context: Option<&'a str>,
+}
+
+/// Result of a single check. Score is between 0 (failed for sure) and 1 (passed for sure).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub check: &'static str,
+    pub score: f32,
+    pub reason: String,
+}
+
+/// Check of an answer generated by LLM.
+pub trait Guardrail: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Scores the answer, or returns `None` when the check does not apply,
+    /// e.g. consistency check of an answer without context.
+    fn check<'a>(
+        &'a self,
+        input: &'a GuardInput<'a>,
+    ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>>;
+}
+
+/// Set of checks run on answers before they are sent.
+pub struct Guardrails {
+    checks: Vec<Box<dyn Guardrail>>,
+    mode: GuardrailMode,
+    min_score: f32,
+}
+
+impl<'a> GuardInput<'a> {
+    pub fn new(answer: &'a str) -> Self {
+        Self {
+            question: None,
+            answer,
+            context: None,
+        }
+    }
+
+    pub fn with_question(mut self, question: &'a str) -> Self {
+        self.question = Some(question);
+        self
+    }
+
+    pub fn with_context(mut self, context: &'a str) -> Self {
+        self.context = Some(context);
+        self
+    }
+}
+
+impl Verdict {
+    pub fn new(check: &'static str, score: f32, reason: impl Into<String>) -> Self {
+        Self {
+            check,
+            score: score.clamp(0.0, 1.0),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Guardrails {
+    /// * `mode`: what happens when a check fails
+    /// * `min_score`: verdicts with lower score are failed
+    pub fn new(mode: GuardrailMode, min_score: f32) -> Self {
+        Self {
+            checks: Vec::new(),
+            mode,
+            min_score,
+        }
+    }
+
+    /// Creates guardrails with checks selected in configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut guardrails = Self::new(config.guardrail_mode, config.guardrail_min_score);
+        for name in config
+            .guardrails
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            let kind =
+                GuardrailKind::from_str(name).map_err(|_| anyhow!("Unknown guardrail '{name}'"))?;
+            match kind {
+                GuardrailKind::Relevance => guardrails.add(RelevanceCheck::new(
+                    Client::with_config(OpenAIConfig::default()),
+                    CHECK_MODEL,
+                )),
+                GuardrailKind::Consistency => guardrails.add(ConsistencyCheck::new(
+                    Client::with_config(OpenAIConfig::default()),
+                    CHECK_MODEL,
+                )),
+                GuardrailKind::Pii => guardrails.add(PiiCheck::new()?),
+                GuardrailKind::Language => match config.guardrail_language {
+                    Some(language) => guardrails.add(LanguageCheck::new(language)),
+                    None => bail!("Language guardrail requires GUARDRAIL_LANGUAGE"),
+                },
+            }
+        }
+
+        Ok(guardrails)
+    }
+
+    pub fn add(&mut self, check: impl Guardrail + 'static) {
+        log::debug!("Adding '{}' guardrail", check.name());
+        self.checks.push(Box::new(check));
+    }
+
+    /// Runs all checks concurrently, results are paired with check names.
+    ///
+    /// * `input`: answer to check
+    async fn evaluate(
+        &self,
+        input: &GuardInput<'_>,
+    ) -> Vec<(&'static str, anyhow::Result<Option<Verdict>>)> {
+        join_all(
+            self.checks
+                .iter()
+                .map(|c| async move { (c.name(), c.check(input).await) }),
+        )
+        .await
+    }
+
+    /// Checks the answer according to the mode. In enforce mode an error is returned
+    /// when any check fails or can not be run, otherwise both are only logged.
+    ///
+    /// * `input`: answer to check
+    pub async fn verify(&self, input: &GuardInput<'_>) -> anyhow::Result<()> {
+        if self.mode == GuardrailMode::Off || self.checks.is_empty() {
+            return Ok(());
+        }
+
+        let mut verdicts = Vec::new();
+        for (name, result) in self.evaluate(input).await {
+            match result {
+                Ok(verdict) => verdicts.extend(verdict),
+                Err(e) if self.mode == GuardrailMode::Enforce => {
+                    return Err(e.context(format!("Guardrail '{name}' can not be run")));
+                }
+                Err(e) => log::warn!("Guardrail '{name}' can not be run, skipping: {e}"),
+            }
+        }
+
+        let failed = verdicts
+            .iter()
+            .filter(|v| v.score < self.min_score)
+            .inspect(|v| {
+                log::warn!(
+                    "Guardrail '{}' failed with score {:.2}: {}",
+                    v.check,
+                    v.score,
+                    v.reason
+                )
+            })
+            .map(|v| v.check)
+            .collect::<Vec<_>>();
+        log::debug!("Guardrail verdicts: {verdicts:?}");
+
+        if self.mode == GuardrailMode::Enforce && !failed.is_empty() {
+            bail!("Answer rejected by guardrails: {}", failed.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Checks answer of a task before it is sent. Non-string answers are checked as JSON text.
+    ///
+    /// * `payload`: task answer payload with `answer` field
+    pub async fn verify_payload(&self, payload: &Value) -> anyhow::Result<()> {
+        let answer = match payload.get("answer").unwrap_or(payload) {
+            Value::String(answer) => answer.clone(),
+            answer => answer.to_string(),
+        };
+        self.verify(&GuardInput::new(&answer)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_guardrails() {
+        let mut guardrails = Guardrails::new(GuardrailMode::Enforce, 0.5);
+        guardrails.add(PiiCheck::new().unwrap());
+        guardrails.add(LanguageCheck::new(Language::Pl));
+
+        let answer = "Spotkanie z klientem jest jutro o dziesiątej w biurze na Mokotowie.";
+        let results = guardrails.evaluate(&GuardInput::new(answer)).await;
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .into_iter()
+            .all(|(_, result)| result.unwrap().unwrap().score == 1.0));
+
+        let leaking =
+            json!({"answer": "Napisz do niego: jan.kowalski@example.com, tel. +48 601 234 567"});
+        assert!(guardrails.verify_payload(&leaking).await.is_err());
+
+        let english = json!({"answer": "The meeting with the client is tomorrow in the office."});
+        let error = guardrails.verify_payload(&english).await.unwrap_err();
+        assert_eq!(error.to_string(), "Answer rejected by guardrails: language");
+    }
+
+    struct BrokenCheck;
+
+    impl Guardrail for BrokenCheck {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+
+        fn check<'a>(
+            &'a self,
+            _input: &'a GuardInput<'a>,
+        ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>> {
+            async { bail!("API unavailable") }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_errors_fail_only_in_enforce_mode() {
+        let input = GuardInput::new("42");
+
+        let mut warn = Guardrails::new(GuardrailMode::Warn, 0.5);
+        warn.add(BrokenCheck);
+        assert!(warn.verify(&input).await.is_ok());
+
+        let mut enforce = Guardrails::new(GuardrailMode::Enforce, 0.5);
+        enforce.add(BrokenCheck);
+        assert!(enforce.verify(&input).await.is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use futures::{future::BoxFuture, FutureExt};
+use strum_macros::{Display, EnumString};
+
+use super::{GuardInput, Guardrail, Verdict};
+
+const POLISH_LETTERS: &str = "ąćęłńóśźż";
+const POLISH_WORDS: &[&str] = &[
+    "i", "w", "z", "na", "się", "nie", "jest", "to", "że", "do", "o", "jak", "ale", "po", "co",
+    "tak", "od", "za", "są", "dla", "oraz", "jego", "jej",
+];
+const ENGLISH_WORDS: &[&str] = &[
+    "the", "is", "and", "of", "to", "in", "it", "that", "for", "with", "are", "was", "on", "as",
+    "this", "be", "at", "by", "not", "or", "from", "his", "her",
+];
+/// Answers with fewer words are too short to detect their language.
+const MIN_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Language {
+    Pl,
+    En,
+}
+
+/// Checks if the answer is written in the expected language, using common words and Polish letters.
+pub struct LanguageCheck {
+    expected: Language,
+}
+
+impl LanguageCheck {
+    pub fn new(expected: Language) -> Self {
+        Self { expected }
+    }
+
+    async fn verify(&self, input: &GuardInput<'_>) -> anyhow::Result<Option<Verdict>> {
+        let text = input.answer.to_lowercase();
+        let words = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>();
+        if words.len() < MIN_WORDS {
+            return Ok(None);
+        }
+
+        let polish = words.iter().filter(|w| POLISH_WORDS.contains(w)).count()
+            + words
+                .iter()
+                .filter(|w| w.chars().any(|c| POLISH_LETTERS.contains(c)))
+                .count();
+        let english = words.iter().filter(|w| ENGLISH_WORDS.contains(w)).count();
+        if polish + english == 0 {
+            return Ok(None);
+        }
+
+        let (expected, other) = match self.expected {
+            Language::Pl => (polish, english),
+            Language::En => (english, polish),
+        };
+        let score = expected as f32 / (expected + other) as f32;
+        let reason = format!(
+            "Expected language {}, matching words {expected}, other {other}",
+            self.expected
+        );
+        Ok(Some(Verdict::new(self.name(), score, reason)))
+    }
+}
+
+impl Guardrail for LanguageCheck {
+    fn name(&self) -> &'static str {
+        "language"
+    }
+
+    fn check<'a>(
+        &'a self,
+        input: &'a GuardInput<'a>,
+    ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>> {
+        self.verify(input).boxed()
+    }
+}
@@ -0,0 +1,111 @@
+use async_openai::{config::OpenAIConfig, Client};
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+
+use super::{GuardInput, Guardrail, Verdict};
+use crate::utils;
+
+const RELEVANCE_CONTEXT: &str = r#"You are a verifier of answers. Rate how well the answer matches the question and whether it is true.
+Return JSON object only: {"score": number from 0 (wrong or off-topic answer) to 1 (correct answer to the question), "reason": "short justification"}"#;
+
+const CONSISTENCY_CONTEXT: &str = r#"You are a verifier of answers. Rate whether the answer is supported by the context provided after ### marker, facts not present in the context lower the score.
+Return JSON object only: {"score": number from 0 (contradicts the context) to 1 (fully supported by the context), "reason": "short justification"}"#;
+
+#[derive(Debug, Deserialize)]
+struct LlmVerdict {
+    score: f32,
+    #[serde(default)]
+    reason: String,
+}
+
+/// Checks with LLM if the answer addresses the question truthfully.
+pub struct RelevanceCheck {
+    client: Client<OpenAIConfig>,
+    model: &'static str,
+}
+
+/// Checks with LLM if the answer is consistent with the context it was generated from.
+pub struct ConsistencyCheck {
+    client: Client<OpenAIConfig>,
+    model: &'static str,
+}
+
+async fn llm_verdict(
+    client: &Client<OpenAIConfig>,
+    model: &str,
+    check: &'static str,
+    instruction: &str,
+    input: &str,
+) -> anyhow::Result<Verdict> {
+    let answer = utils::ask_llm(client, model, input, Some(instruction)).await?;
+    let verdict: LlmVerdict = utils::parse_json_answer(&answer)?;
+    Ok(Verdict::new(check, verdict.score, verdict.reason))
+}
+
+impl RelevanceCheck {
+    pub fn new(client: Client<OpenAIConfig>, model: &'static str) -> Self {
+        Self { client, model }
+    }
+
+    async fn verify(&self, input: &GuardInput<'_>) -> anyhow::Result<Option<Verdict>> {
+        let Some(question) = input.question else {
+            return Ok(None);
+        };
+        let prompt = format!("Question: {question}\nAnswer: {}", input.answer);
+        let verdict = llm_verdict(
+            &self.client,
+            self.model,
+            self.name(),
+            RELEVANCE_CONTEXT,
+            &prompt,
+        )
+        .await?;
+        Ok(Some(verdict))
+    }
+}
+
+impl ConsistencyCheck {
+    pub fn new(client: Client<OpenAIConfig>, model: &'static str) -> Self {
+        Self { client, model }
+    }
+
+    async fn verify(&self, input: &GuardInput<'_>) -> anyhow::Result<Option<Verdict>> {
+        let Some(context) = input.context else {
+            return Ok(None);
+        };
+        let instruction = format!("{CONSISTENCY_CONTEXT}\n###\n{context}");
+        let prompt = match input.question {
+            Some(question) => format!("Question: {question}\nAnswer: {}", input.answer),
+            None => format!("Answer: {}", input.answer),
+        };
+        let verdict =
+            llm_verdict(&self.client, self.model, self.name(), &instruction, &prompt).await?;
+        Ok(Some(verdict))
+    }
+}
+
+impl Guardrail for RelevanceCheck {
+    fn name(&self) -> &'static str {
+        "relevance"
+    }
+
+    fn check<'a>(
+        &'a self,
+        input: &'a GuardInput<'a>,
+    ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>> {
+        self.verify(input).boxed()
+    }
+}
+
+impl Guardrail for ConsistencyCheck {
+    fn name(&self) -> &'static str {
+        "consistency"
+    }
+
+    fn check<'a>(
+        &'a self,
+        input: &'a GuardInput<'a>,
+    ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>> {
+        self.verify(input).boxed()
+    }
+}
@@ -0,0 +1,59 @@
+use futures::{future::BoxFuture, FutureExt};
+
+use super::{GuardInput, Guardrail, Verdict};
+use crate::anonymizer::PatternDetector;
+
+/// Checks if the answer contains personal data: e-mail addresses, phone numbers,
+/// PESEL numbers or bank account numbers. Found values are never logged.
+pub struct PiiCheck {
+    detector: PatternDetector,
+}
+
+impl PiiCheck {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            detector: PatternDetector::new()?,
+        })
+    }
+
+    async fn verify(&self, input: &GuardInput<'_>) -> anyhow::Result<Option<Verdict>> {
+        let mut found = self
+            .detector
+            .detect(input.answer)
+            .into_iter()
+            .map(|detection| detection.kind)
+            .collect::<Vec<_>>();
+        found.sort();
+        found.dedup();
+
+        let verdict = match found.is_empty() {
+            true => Verdict::new(self.name(), 1.0, "No personal data found"),
+            false => Verdict::new(
+                self.name(),
+                0.0,
+                format!(
+                    "Personal data found: {}",
+                    found
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+        };
+        Ok(Some(verdict))
+    }
+}
+
+impl Guardrail for PiiCheck {
+    fn name(&self) -> &'static str {
+        "pii"
+    }
+
+    fn check<'a>(
+        &'a self,
+        input: &'a GuardInput<'a>,
+    ) -> BoxFuture<'a, anyhow::Result<Option<Verdict>>> {
+        self.verify(input).boxed()
+    }
+}
@@ -0,0 +1,249 @@
+mod country;
+mod currency_rate;
+mod date;
+mod llm;
+
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        ChatCompletionMessageToolCall, ChatCompletionRequestMessage,
+        ChatCompletionRequestToolMessageArgs, ChatCompletionTool, ChatCompletionToolArgs,
+        ChatCompletionToolType, FunctionCall, FunctionObjectArgs,
+    },
+    Client,
+};
+use futures::future::{join_all, BoxFuture};
+use serde_json::{json, Value};
+use strum_macros::{Display, EnumString};
+
+use crate::{
+    config::Config,
+    data_source::{DataSource, DataSourceKind, FixtureDataSource, HttpDataSource},
+    exchange_rate::ExchangeRates,
+    fetcher::Fetcher,
+};
+
+use country::CountryTool;
+use currency_rate::{CurrencyConversionTool, CurrencyRateTool};
+use date::DateTool;
+use llm::LlmTool;
+
+/// Model used by the LLM knowledge tool.
+const LLM_TOOL_MODEL: &str = "gpt-3.5-turbo";
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum KnowledgeToolKind {
+    /// Country facts from REST Countries
+    Country,
+    /// Currency rates from NBP
+    CurrencyRate,
+    /// Currency conversions with NBP rates
+    CurrencyConversion,
+    /// Relative date resolution
+    Date,
+    /// LLM base knowledge
+    Llm,
+}
+
+/// Tool answering questions with data from external source, called by LLM with function calling.
+pub trait KnowledgeTool: Send + Sync {
+    /// Function name presented to LLM
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// JSON schema of function arguments.
+    fn parameters(&self) -> Value;
+
+    /// Calls the tool with arguments generated by LLM and returns the answer.
+    ///
+    /// * `args`: JSON object matching the parameters schema
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>>;
+}
+
+/// Set of tools offered to LLM.
+#[derive(Default)]
+pub struct KnowledgeToolRegistry {
+    tools: Vec<Box<dyn KnowledgeTool>>,
+}
+
+impl KnowledgeToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates registry with tools and data source selected in configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let source: Arc<dyn DataSource> = match config.knowledge_data_source {
+            DataSourceKind::Http => Arc::new(HttpDataSource::new(Fetcher::from_config(config)?)),
+            DataSourceKind::Fixtures => Arc::new(FixtureDataSource::from_file(
+                &config.knowledge_fixtures_path,
+            )?),
+        };
+        log::info!("Using '{}' knowledge data source", source.name());
+
+        // Fixture tables must not end up in the cache of real ones
+        let cache_path = match config.knowledge_data_source {
+            DataSourceKind::Http => Some(config.exchange_rate_cache.clone()),
+            DataSourceKind::Fixtures => None,
+        };
+        let rates = Arc::new(ExchangeRates::new(source.clone(), cache_path)?);
+
+        let mut registry = Self::new();
+        for name in config
+            .knowledge_tools
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            let kind = KnowledgeToolKind::from_str(name)
+                .map_err(|_| anyhow!("Unknown knowledge tool '{name}'"))?;
+            match kind {
+                KnowledgeToolKind::Country => registry.register(CountryTool::new(source.clone())),
+                KnowledgeToolKind::CurrencyRate => {
+                    registry.register(CurrencyRateTool::new(rates.clone()))
+                }
+                KnowledgeToolKind::CurrencyConversion => {
+                    registry.register(CurrencyConversionTool::new(rates.clone()))
+                }
+                KnowledgeToolKind::Date => registry.register(DateTool),
+                KnowledgeToolKind::Llm => registry.register(LlmTool::new(
+                    Client::with_config(OpenAIConfig::default()),
+                    LLM_TOOL_MODEL,
+                )),
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, tool: impl KnowledgeTool + 'static) {
+        log::debug!("Registering '{}' knowledge tool", tool.name());
+        self.tools.push(Box::new(tool));
+    }
+
+    /// Tool definitions for chat completion request.
+    pub fn chat_tools(&self) -> anyhow::Result<Vec<ChatCompletionTool>> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                let function = FunctionObjectArgs::default()
+                    .name(tool.name())
+                    .description(tool.description())
+                    .parameters(tool.parameters())
+                    .build()?;
+                let chat_tool = ChatCompletionToolArgs::default()
+                    .r#type(ChatCompletionToolType::Function)
+                    .function(function)
+                    .build()?;
+                Ok(chat_tool)
+            })
+            .collect()
+    }
+
+    /// Calls tool requested by LLM.
+    ///
+    /// * `function_call`: function name and JSON arguments generated by LLM
+    pub async fn call(&self, function_call: &FunctionCall) -> anyhow::Result<Value> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.name() == function_call.name)
+            .ok_or(anyhow!("Function '{}' does not exists", function_call.name))?;
+        let args = Value::from_str(&function_call.arguments)?;
+
+        log::debug!("Calling '{}' with args: {args:?}", function_call.name);
+        tool.call(args).await
+    }
+
+    /// Calls all tools requested by LLM concurrently and returns tool messages with their results,
+    /// in order of the calls. Failed calls are reported to LLM as errors, so it can answer anyway.
+    ///
+    /// * `tool_calls`: tool calls from LLM response
+    pub async fn call_all(
+        &self,
+        tool_calls: &[ChatCompletionMessageToolCall],
+    ) -> anyhow::Result<Vec<ChatCompletionRequestMessage>> {
+        let results = join_all(tool_calls.iter().map(|c| self.call(&c.function))).await;
+
+        tool_calls
+            .iter()
+            .zip(results)
+            .map(|(tool_call, result)| {
+                let content = match result {
+                    Ok(value) => json!({ "result": value }),
+                    Err(err) => {
+                        log::warn!("Function '{}' failed: {err}", tool_call.function.name);
+                        json!({ "error": err.to_string() })
+                    }
+                };
+                let message = ChatCompletionRequestToolMessageArgs::default()
+                    .tool_call_id(&tool_call.id)
+                    .content(content.to_string())
+                    .build()?;
+                Ok(message.into())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_call(id: &str, name: &str, arguments: Value) -> ChatCompletionMessageToolCall {
+        ChatCompletionMessageToolCall {
+            id: id.to_string(),
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_all_tools() {
+        let source = Arc::new(FixtureDataSource::from_file("fixtures/knowledge.json").unwrap());
+        let mut registry = KnowledgeToolRegistry::new();
+        registry.register(CountryTool::new(source));
+
+        let tool_calls = [
+            tool_call(
+                "call_1",
+                "get_country_attribute",
+                json!({"country": "Poland", "attribute": "population"}),
+            ),
+            tool_call(
+                "call_2",
+                "get_country_attribute",
+                json!({"country": "France", "attribute": "population"}),
+            ),
+            tool_call("call_3", "get_weather", json!({})),
+        ];
+        let messages = registry.call_all(&tool_calls).await.unwrap();
+
+        let contents = messages
+            .into_iter()
+            .map(|m| match m {
+                ChatCompletionRequestMessage::Tool(m) => (m.tool_call_id, m.content),
+                _ => panic!("Not a tool message"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents[0],
+            ("call_1".into(), r#"{"result":37950802}"#.into())
+        );
+        assert_eq!(
+            contents[1],
+            ("call_2".into(), r#"{"result":67391582}"#.into())
+        );
+        assert!(contents[2].1.contains("error"));
+    }
+}
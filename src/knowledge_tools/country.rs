@@ -0,0 +1,176 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::KnowledgeTool;
+use crate::data_source::DataSource;
+
+const API_BASE_URL: &str = "https://restcountries.com/v3.1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CountryAttribute {
+    Population,
+    Capital,
+    /// Area in square kilometres
+    Area,
+    Region,
+    Subregion,
+    Languages,
+    Currencies,
+    Timezones,
+    Borders,
+    OfficialName,
+}
+
+#[derive(Debug, Deserialize)]
+struct CountryArgs {
+    country: String,
+    attribute: CountryAttribute,
+}
+
+#[derive(Debug, Deserialize)]
+struct CountryName {
+    common: String,
+    official: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CountryCurrency {
+    name: String,
+}
+
+/// Country as returned by REST Countries API, only fields used by the tool.
+#[derive(Debug, Deserialize)]
+struct Country {
+    name: CountryName,
+    #[serde(default)]
+    capital: Vec<String>,
+    area: f64,
+    population: u64,
+    region: String,
+    #[serde(default)]
+    subregion: Option<String>,
+    #[serde(default)]
+    languages: BTreeMap<String, String>,
+    #[serde(default)]
+    currencies: BTreeMap<String, CountryCurrency>,
+    #[serde(default)]
+    timezones: Vec<String>,
+    #[serde(default)]
+    borders: Vec<String>,
+}
+
+/// Answers questions about country facts with REST Countries API.
+pub struct CountryTool {
+    source: Arc<dyn DataSource>,
+}
+
+impl CountryTool {
+    pub fn new(source: Arc<dyn DataSource>) -> Self {
+        Self { source }
+    }
+
+    async fn country_attribute(&self, args: Value) -> anyhow::Result<Value> {
+        let args: CountryArgs = serde_json::from_value(args)?;
+        let name = args.country.trim().to_lowercase();
+        let url = format!("{API_BASE_URL}/name/{name}");
+
+        let countries: Vec<Country> = match self.source.get(&url).await? {
+            Some(response) => serde_json::from_value(response)?,
+            None => Vec::new(),
+        };
+        // Name search matches also partial names, e.g. "Sudan" and "South Sudan"
+        let country = countries
+            .iter()
+            .find(|c| c.name.common.to_lowercase() == name)
+            .or(countries.first())
+            .ok_or(anyhow!("Country '{}' not found", args.country))?;
+
+        let value = match args.attribute {
+            CountryAttribute::Population => json!(country.population),
+            CountryAttribute::Capital => json!(country.capital.join(", ")),
+            CountryAttribute::Area => json!(country.area),
+            CountryAttribute::Region => json!(country.region),
+            CountryAttribute::Subregion => json!(country.subregion),
+            CountryAttribute::Languages => json!(country.languages.values().collect::<Vec<_>>()),
+            CountryAttribute::Currencies => json!(country
+                .currencies
+                .iter()
+                .map(|(code, currency)| format!("{} ({code})", currency.name))
+                .collect::<Vec<_>>()),
+            CountryAttribute::Timezones => json!(country.timezones),
+            CountryAttribute::Borders => json!(country.borders),
+            CountryAttribute::OfficialName => json!(country.name.official),
+        };
+
+        Ok(value)
+    }
+}
+
+impl KnowledgeTool for CountryTool {
+    fn name(&self) -> &'static str {
+        "get_country_attribute"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get country facts: population, capital, area (km²), region, languages, currencies, timezones, borders or official name"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "country": {
+                    "type": "string",
+                    "description": "Country name (in english)"
+                },
+                "attribute": {
+                    "type": "string",
+                    "enum": [
+                        "population", "capital", "area", "region", "subregion", "languages",
+                        "currencies", "timezones", "borders", "official_name"
+                    ]
+                },
+            },
+            "required": ["country", "attribute"]
+        })
+    }
+
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>> {
+        self.country_attribute(args).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data_source::FixtureDataSource;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_country_attributes_from_fixtures() {
+        let source = FixtureDataSource::from_file("fixtures/knowledge.json").unwrap();
+        let tool = CountryTool::new(Arc::new(source));
+
+        let population = tool
+            .call(json!({"country": "Poland", "attribute": "population"}))
+            .await
+            .unwrap();
+        assert_eq!(population, json!(37950802));
+
+        let capital = tool
+            .call(json!({"country": "poland", "attribute": "capital"}))
+            .await
+            .unwrap();
+        assert_eq!(capital, json!("Warsaw"));
+
+        let missing = tool
+            .call(json!({"country": "Atlantis", "attribute": "area"}))
+            .await;
+        assert!(missing.is_err());
+    }
+}
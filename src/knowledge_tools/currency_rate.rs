@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use futures::{future::BoxFuture, FutureExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::KnowledgeTool;
+use crate::exchange_rate::{ExchangeRates, RateKind};
+
+#[derive(Debug, Deserialize)]
+struct CurrencyRateArgs {
+    currency_code: String,
+    #[serde(default)]
+    date: Option<NaiveDate>,
+    #[serde(default)]
+    rate_type: RateKind,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrencyConversionArgs {
+    amount: Decimal,
+    from: String,
+    to: String,
+    #[serde(default)]
+    date: Option<NaiveDate>,
+}
+
+/// Answers questions about currency rates to Polish Złoty with NBP (National Bank of Poland) API.
+pub struct CurrencyRateTool {
+    rates: Arc<ExchangeRates>,
+}
+
+/// Converts amounts between currencies with NBP average rates.
+pub struct CurrencyConversionTool {
+    rates: Arc<ExchangeRates>,
+}
+
+impl CurrencyRateTool {
+    pub fn new(rates: Arc<ExchangeRates>) -> Self {
+        Self { rates }
+    }
+
+    async fn currency_rate(&self, args: Value) -> anyhow::Result<Value> {
+        let args: CurrencyRateArgs = serde_json::from_value(args)?;
+        let rate = self
+            .rates
+            .rate(args.currency_code.trim(), args.date, args.rate_type)
+            .await?;
+        log::debug!(
+            "{} rate from table {} published {}",
+            rate.code,
+            rate.table,
+            rate.date
+        );
+
+        Ok(json!(rate.value))
+    }
+}
+
+impl CurrencyConversionTool {
+    pub fn new(rates: Arc<ExchangeRates>) -> Self {
+        Self { rates }
+    }
+
+    async fn convert(&self, args: Value) -> anyhow::Result<Value> {
+        let args: CurrencyConversionArgs = serde_json::from_value(args)?;
+        let conversion = self
+            .rates
+            .convert(args.amount, args.from.trim(), args.to.trim(), args.date)
+            .await?;
+
+        Ok(json!(conversion))
+    }
+}
+
+impl KnowledgeTool for CurrencyRateTool {
+    fn name(&self) -> &'static str {
+        "get_currency_rate"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get currency rate to Polish Złoty (PLN), current or historical"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "currency_code": {
+                    "type": "string",
+                    "description": "ISO 4217 currency code, e.g. USD"
+                },
+                "date": {
+                    "type": "string",
+                    "description": "Date in YYYY-MM-DD format, only when question is about historical rate"
+                },
+                "rate_type": {
+                    "type": "string",
+                    "enum": ["mid", "bid", "ask"],
+                    "description": "Average (mid), buying (bid) or selling (ask) rate, mid by default"
+                },
+            },
+            "required": ["currency_code"]
+        })
+    }
+
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>> {
+        self.currency_rate(args).boxed()
+    }
+}
+
+impl KnowledgeTool for CurrencyConversionTool {
+    fn name(&self) -> &'static str {
+        "convert_currency"
+    }
+
+    fn description(&self) -> &'static str {
+        "Convert amount of money between any two currencies, including PLN, current or historical"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "amount": {
+                    "type": "number",
+                    "description": "Amount in source currency"
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Source ISO 4217 currency code, e.g. USD"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Target ISO 4217 currency code, e.g. EUR"
+                },
+                "date": {
+                    "type": "string",
+                    "description": "Date in YYYY-MM-DD format, only when question is about historical rate"
+                },
+            },
+            "required": ["amount", "from", "to"]
+        })
+    }
+
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>> {
+        self.convert(args).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::data_source::FixtureDataSource;
+
+    use super::*;
+
+    fn rates() -> Arc<ExchangeRates> {
+        let source = FixtureDataSource::new(HashMap::from([
+            (
+                "https://api.nbp.pl/api/exchangerates/tables/a/".to_string(),
+                json!([{
+                    "table": "A",
+                    "no": "005/A/NBP/2024",
+                    "effectiveDate": "2024-01-09",
+                    "rates": [{"currency": "euro", "code": "EUR", "mid": 4.3574}]
+                }]),
+            ),
+            (
+                "https://api.nbp.pl/api/exchangerates/tables/c/".to_string(),
+                json!([{
+                    "table": "C",
+                    "no": "005/C/NBP/2024",
+                    "tradingDate": "2024-01-08",
+                    "effectiveDate": "2024-01-09",
+                    "rates": [{"currency": "euro", "code": "EUR", "bid": 4.3273, "ask": 4.4147}]
+                }]),
+            ),
+        ]));
+        Arc::new(ExchangeRates::new(Arc::new(source), None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_tool_arguments_and_output() {
+        let tool = CurrencyRateTool::new(rates());
+        let mid = tool.call(json!({"currency_code": " eur "})).await.unwrap();
+        assert_eq!(mid, json!(Decimal::new(43574, 4)));
+
+        let ask = tool
+            .call(json!({"currency_code": "EUR", "rate_type": "ask"}))
+            .await
+            .unwrap();
+        assert_eq!(ask, json!(Decimal::new(44147, 4)));
+
+        assert!(tool.call(json!({"rate_type": "mid"})).await.is_err());
+        assert!(tool
+            .call(json!({"currency_code": "EUR", "rate_type": "average"}))
+            .await
+            .is_err());
+
+        let tool = CurrencyConversionTool::new(rates());
+        let conversion = tool
+            .call(json!({"amount": 10, "from": "EUR ", "to": "pln"}))
+            .await
+            .unwrap();
+        assert_eq!(conversion["from"], json!("EUR"));
+        assert_eq!(conversion["result"], json!(Decimal::new(435740, 4)));
+
+        assert!(tool
+            .call(json!({"amount": "ten", "from": "EUR", "to": "PLN"}))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::KnowledgeTool;
+use crate::temporal;
+
+#[derive(Debug, Deserialize)]
+struct DateArgs {
+    expression: String,
+}
+
+/// Resolves relative date expressions, so LLM does not have to compute dates itself.
+pub struct DateTool;
+
+impl DateTool {
+    async fn resolve(&self, args: Value) -> anyhow::Result<Value> {
+        let args: DateArgs = serde_json::from_value(args)?;
+        let today = temporal::today();
+        let date = temporal::resolve_date(&args.expression, today)
+            .ok_or(anyhow!("Can not resolve date '{}'", args.expression))?;
+
+        Ok(json!({
+            "date": date,
+            "weekday": temporal::weekday_name(date),
+            "days_from_today": (date - today).num_days(),
+        }))
+    }
+}
+
+impl KnowledgeTool for DateTool {
+    fn name(&self) -> &'static str {
+        "resolve_date"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get exact date and weekday of today or relative date expression, e.g. 'tomorrow', 'next friday', 'in two weeks', 'w przyszły poniedziałek'"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Date expression in Polish or English, e.g. 'today' or 'za tydzień'"
+                },
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>> {
+        self.resolve(args).boxed()
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::anyhow;
+use async_openai::{config::OpenAIConfig, Client};
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::KnowledgeTool;
+use crate::utils;
+
+#[derive(Debug, Deserialize)]
+struct LlmArgs {
+    question: String,
+}
+
+/// Answers general knowledge questions with LLM base knowledge.
+pub struct LlmTool {
+    client: Client<OpenAIConfig>,
+    model: &'static str,
+}
+
+impl LlmTool {
+    pub fn new(client: Client<OpenAIConfig>, model: &'static str) -> Self {
+        Self { client, model }
+    }
+
+    async fn ask(&self, args: Value) -> anyhow::Result<Value> {
+        let args: LlmArgs = serde_json::from_value(args)?;
+        if args.question.trim().is_empty() {
+            return Err(anyhow!("Question is empty"));
+        }
+
+        let answer = utils::ask_llm(&self.client, self.model, &args.question, None).await?;
+        Ok(json!(answer))
+    }
+}
+
+impl KnowledgeTool for LlmTool {
+    fn name(&self) -> &'static str {
+        "ask_llm"
+    }
+
+    fn description(&self) -> &'static str {
+        "Ask LLM base knowledge"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "Question"
+                },
+            },
+            "required": ["question"]
+        })
+    }
+
+    fn call(&self, args: Value) -> BoxFuture<'_, anyhow::Result<Value>> {
+        self.ask(args).boxed()
+    }
+}
@@ -1,10 +1,31 @@
 mod aidevs;
+mod anonymizer;
 mod brave_search;
 mod cli;
 mod config;
+mod data_source;
+mod entities;
+mod evidence;
+mod exchange_rate;
+mod extract;
+mod fetcher;
+mod guardrails;
+mod knowledge_tools;
+mod meme_renderer;
+mod memory;
+mod moderation;
+mod organizer;
 mod render_form;
+mod search_provider;
+mod serve;
+mod server;
+mod session;
 mod tasks;
+mod temporal;
+mod transcribe;
+mod tunnel;
 mod utils;
+mod vision;
 
 use std::env;
 
@@ -12,7 +33,10 @@ use clap::Parser;
 use dotenv::dotenv;
 use envconfig::Envconfig;
 
-use crate::{cli::Cli, config::Config};
+use crate::{
+    cli::{Cli, Command},
+    config::Config,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -24,10 +48,15 @@ async fn main() -> anyhow::Result<()> {
     let config = Config::init_from_env()?;
     let cli = Cli::parse();
 
-    if cli.hint {
-        cli.task.hint(config).await?;
-        return Ok(());
+    match cli.command {
+        Command::Task(task) if cli.hint => task.hint(config).await,
+        Command::Task(task) => task.run(config).await,
+        Command::Serve(args) => serve::run(&config, args).await,
+        Command::Transcribe(args) => transcribe::run(&config, args).await,
+        Command::DescribeImage(args) => vision::run(args).await,
+        Command::RenderForm(args) => render_form::run(&config, args).await,
+        Command::Rate(args) => exchange_rate::run(&config, args).await,
+        Command::Anonymize(args) => anonymizer::run(&config, args).await,
+        Command::BraveSearch(args) => brave_search::run(&config, args).await,
     }
-
-    cli.task.run(config).await
 }
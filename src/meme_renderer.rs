@@ -0,0 +1,298 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use ab_glyph::{FontVec, PxScale};
+use anyhow::{anyhow, bail};
+use image::{imageops, DynamicImage, ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use serde::Deserialize;
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+/// Template used when templates file does not define one with this name.
+const DEFAULT_TEMPLATE_NAME: &str = "default";
+/// Built-in default template. It resembles the RenderForm template
+/// used by the meme task: picture on black background with a white caption below.
+const DEFAULT_TEMPLATE: &str = r##"{
+    "name": "default",
+    "width": 1080,
+    "height": 1080,
+    "background": "#000000",
+    "image": { "x": 60, "y": 60, "width": 960, "height": 760 },
+    "caption": {
+        "area": { "x": 60, "y": 850, "width": 960, "height": 180 },
+        "font": "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+        "font_size": 64,
+        "color": "#ffffff"
+    }
+}"##;
+/// Caption font is shrunk by this factor until the text fits its area.
+const FONT_SHRINK_FACTOR: f32 = 0.9;
+const MIN_FONT_SIZE: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum MemeBackend {
+    /// RenderForm API, requires API key
+    RenderForm,
+    /// Rendered locally and hosted by the API server
+    Local,
+}
+
+/// Rectangle on the meme canvas, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Area {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Caption {
+    pub area: Area,
+    /// Path to TTF or OTF font file
+    pub font: PathBuf,
+    /// Maximal font size in pixels, reduced for long captions
+    pub font_size: f32,
+    /// Hex RGB color, e.g. `#ffffff`
+    pub color: String,
+}
+
+/// Meme layout: canvas with picture and caption areas.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemeTemplate {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Hex RGB color, e.g. `#000000`
+    pub background: String,
+    pub image: Area,
+    pub caption: Caption,
+}
+
+/// Renders memes locally, with the template selected in configuration.
+pub struct MemeRenderer {
+    template: MemeTemplate,
+    font: FontVec,
+}
+
+impl MemeTemplate {
+    /// Loads template by name from JSON file with a list of templates.
+    /// Built-in default template is used when the file does not exist or does not override it.
+    ///
+    /// * `path`: templates file path
+    /// * `name`: template name
+    pub fn load(path: &Path, name: &str) -> anyhow::Result<Self> {
+        let templates: Vec<MemeTemplate> = match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| anyhow!("Invalid meme templates file {}: {e}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                log::debug!(
+                    "{} not found, only default template available",
+                    path.display()
+                );
+                Vec::new()
+            }
+            Err(err) => bail!("Can not read {}: {err}", path.display()),
+        };
+
+        match templates.into_iter().find(|t| t.name == name) {
+            Some(template) => Ok(template),
+            None if name == DEFAULT_TEMPLATE_NAME => Ok(serde_json::from_str(DEFAULT_TEMPLATE)?),
+            None => bail!("Meme template '{name}' not found"),
+        }
+    }
+}
+
+impl MemeRenderer {
+    pub fn new(template: MemeTemplate) -> anyhow::Result<Self> {
+        let font_data = std::fs::read(&template.caption.font)
+            .map_err(|e| anyhow!("Can not read font {}: {e}", template.caption.font.display()))?;
+        let font = FontVec::try_from_vec(font_data)?;
+
+        Ok(Self { template, font })
+    }
+
+    /// Creates renderer with the template selected in configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let template = MemeTemplate::load(&config.meme_templates, &config.meme_template)?;
+        log::info!("Using '{}' meme template", template.name);
+
+        Self::new(template)
+    }
+
+    /// Composes the picture and caption and returns PNG image.
+    ///
+    /// * `image`: picture file content (PNG, JPEG or WebP)
+    /// * `text`: caption text
+    pub fn render(&self, image: &[u8], text: &str) -> anyhow::Result<Vec<u8>> {
+        let template = &self.template;
+        let background = parse_color(&template.background)?;
+        let mut canvas = RgbaImage::from_pixel(template.width, template.height, background);
+
+        let picture = image::load_from_memory(image)?;
+        let (x, y, width, height) = fit_into(picture.width(), picture.height(), template.image);
+        let picture = picture.resize_exact(width, height, imageops::FilterType::Lanczos3);
+        imageops::overlay(&mut canvas, &picture.to_rgba8(), x.into(), y.into());
+
+        self.draw_caption(&mut canvas, text)?;
+
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(canvas).write_to(&mut png, ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+
+    /// Draws caption centered in its area. Font is shrunk until the wrapped text fits.
+    fn draw_caption(&self, canvas: &mut RgbaImage, text: &str) -> anyhow::Result<()> {
+        let caption = &self.template.caption;
+        let color = parse_color(&caption.color)?;
+
+        let mut font_size = caption.font_size;
+        let lines = loop {
+            let scale = PxScale::from(font_size);
+            let lines = self.wrap_text(text, scale, caption.area.width);
+            let height = lines.len() as f32 * font_size;
+            if height <= caption.area.height as f32 || font_size <= MIN_FONT_SIZE {
+                break lines;
+            }
+            font_size = (font_size * FONT_SHRINK_FACTOR).max(MIN_FONT_SIZE);
+        };
+
+        let scale = PxScale::from(font_size);
+        let text_height = lines.len() as f32 * font_size;
+        let mut y =
+            caption.area.y as f32 + (caption.area.height as f32 - text_height).max(0.0) / 2.0;
+        for line in lines {
+            let (line_width, _) = text_size(scale, &self.font, &line);
+            let x =
+                caption.area.x as i32 + (caption.area.width as i32 - line_width as i32).max(0) / 2;
+            draw_text_mut(canvas, color, x, y as i32, scale, &self.font, &line);
+            y += font_size;
+        }
+
+        Ok(())
+    }
+
+    /// Splits text into lines not wider than `width`. Words longer than a line are not split.
+    fn wrap_text(&self, text: &str, scale: PxScale, width: u32) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let candidate = match line.is_empty() {
+                true => word.to_string(),
+                false => format!("{line} {word}"),
+            };
+            if !line.is_empty() && text_size(scale, &self.font, &candidate).0 > width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+/// Saves rendered meme in the directory and returns its file name.
+///
+/// * `dir`: output directory, created when missing
+/// * `png`: PNG image
+pub fn save(dir: &Path, png: &[u8]) -> anyhow::Result<String> {
+    std::fs::create_dir_all(dir)?;
+    let file_name = format!("meme-{}.png", chrono::Utc::now().timestamp_millis());
+    std::fs::write(dir.join(&file_name), png)?;
+
+    Ok(file_name)
+}
+
+/// Scales picture to fit the area keeping its aspect ratio and centers it.
+/// Returns position and size of the scaled picture.
+fn fit_into(width: u32, height: u32, area: Area) -> (u32, u32, u32, u32) {
+    let scale = f64::min(
+        area.width as f64 / width.max(1) as f64,
+        area.height as f64 / height.max(1) as f64,
+    );
+    let fitted_width = ((width as f64 * scale).round() as u32).clamp(1, area.width);
+    let fitted_height = ((height as f64 * scale).round() as u32).clamp(1, area.height);
+
+    (
+        area.x + (area.width - fitted_width) / 2,
+        area.y + (area.height - fitted_height) / 2,
+        fitted_width,
+        fitted_height,
+    )
+}
+
+/// Parses `#rrggbb` color.
+fn parse_color(color: &str) -> anyhow::Result<Rgba<u8>> {
+    let hex = color.trim_start_matches('#');
+    let value = match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        _ => None,
+    }
+    .ok_or(anyhow!("Invalid color '{color}', expected #rrggbb"))?;
+
+    Ok(Rgba([
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+        255,
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_picture_into_area() {
+        let area = Area {
+            x: 10,
+            y: 20,
+            width: 200,
+            height: 100,
+        };
+
+        assert_eq!(fit_into(400, 100, area), (10, 45, 200, 50));
+        assert_eq!(fit_into(50, 100, area), (85, 20, 50, 100));
+        assert_eq!(parse_color("#ff8000").unwrap(), Rgba([255, 128, 0, 255]));
+        assert!(parse_color("white").is_err());
+    }
+
+    #[test]
+    fn test_render_default_template() {
+        let template = MemeTemplate::load(Path::new("meme_templates.json"), "default").unwrap();
+        let renderer = MemeRenderer::new(template).unwrap();
+
+        let mut picture = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 255])))
+            .write_to(&mut picture, ImageFormat::Png)
+            .unwrap();
+        let png = renderer
+            .render(
+                picture.get_ref(),
+                "Kiedy testy przechodzą za pierwszym razem",
+            )
+            .unwrap();
+
+        let meme = image::load_from_memory(&png).unwrap();
+        assert_eq!((meme.width(), meme.height()), (1080, 1080));
+        // Picture is scaled to the image area width and centered vertically
+        assert_eq!(meme.to_rgba8().get_pixel(540, 440), &Rgba([255, 0, 0, 255]));
+
+        let dir = std::env::temp_dir().join(format!("memes-{}", std::process::id()));
+        let file_name = save(&dir, &png).unwrap();
+        assert!(file_name.starts_with("meme-") && file_name.ends_with(".png"));
+        assert_eq!(std::fs::read(dir.join(&file_name)).unwrap(), png);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
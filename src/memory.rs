@@ -0,0 +1,245 @@
+mod file;
+mod vector;
+
+use anyhow::anyhow;
+use async_openai::{config::OpenAIConfig, Client};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use qdrant_client::client::QdrantClient;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::{config::Config, utils};
+
+pub use file::FileMemoryStore;
+pub use vector::QdrantMemoryStore;
+
+/// Minimal similarity of a fact to the question to pass it to LLM.
+const RECALL_MIN_SCORE: f32 = 0.75;
+/// Minimal similarity of a fact to the description given by user to forget or update it.
+const MATCH_MIN_SCORE: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum MemoryBackend {
+    Qdrant,
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Fact {
+    pub id: u64,
+    /// Conversation session which the fact belongs to
+    #[serde(default)]
+    pub session: String,
+    pub category: String,
+    pub data: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoredFact {
+    pub fact: Fact,
+    pub score: f32,
+}
+
+/// Storage of facts and their embeddings.
+pub trait MemoryStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Inserts the fact or replaces stored fact with the same id.
+    fn upsert<'a>(
+        &'a self,
+        fact: &'a Fact,
+        embedding: Vec<f32>,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Returns at most `limit` facts of the session most similar to the embedding, best matching first.
+    fn search<'a>(
+        &'a self,
+        session: &'a str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<ScoredFact>>>;
+
+    fn delete(&self, id: u64) -> BoxFuture<'_, anyhow::Result<()>>;
+}
+
+/// Long-term memory of facts, retrieved by semantic similarity to the question.
+/// Facts are separated per conversation session and survive session expiry.
+pub struct Memory {
+    store: Box<dyn MemoryStore>,
+    openai_client: Client<OpenAIConfig>,
+}
+
+/// Creates memory store selected in configuration.
+/// When backend is not set explicitly, Qdrant is used if its URL is present, JSON file otherwise.
+///
+/// * `config`: App configuration
+pub async fn from_config(config: &Config) -> anyhow::Result<Box<dyn MemoryStore>> {
+    let backend = match config.memory_backend {
+        Some(backend) => backend,
+        None if config.qdrant_url.is_some() => MemoryBackend::Qdrant,
+        None => MemoryBackend::File,
+    };
+    log::info!("Using '{backend}' memory store");
+
+    let store: Box<dyn MemoryStore> = match backend {
+        MemoryBackend::Qdrant => {
+            let qdrant_url = config
+                .qdrant_url
+                .as_ref()
+                .ok_or(anyhow!("Qdrant URL not found in configuration"))?;
+            let client = QdrantClient::from_url(qdrant_url.as_str()).build()?;
+            Box::new(QdrantMemoryStore::new(client, &config.memory_collection).await?)
+        }
+        MemoryBackend::File => Box::new(FileMemoryStore::open(&config.memory_file)?),
+    };
+
+    Ok(store)
+}
+
+impl Fact {
+    fn embedding_input(&self) -> String {
+        format!("{}: {}", self.category, self.data)
+    }
+}
+
+impl Memory {
+    pub fn new(store: Box<dyn MemoryStore>, openai_client: Client<OpenAIConfig>) -> Self {
+        Self {
+            store,
+            openai_client,
+        }
+    }
+
+    pub async fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let store = from_config(config).await?;
+        Ok(Self::new(
+            store,
+            Client::with_config(OpenAIConfig::default()),
+        ))
+    }
+
+    /// Stores a new fact.
+    ///
+    /// * `session`: conversation session id
+    /// * `category`: fact category, e.g. 'hobby'
+    /// * `data`: fact content
+    pub async fn remember(
+        &self,
+        session: &str,
+        category: &str,
+        data: &str,
+    ) -> anyhow::Result<Fact> {
+        let now = Utc::now();
+        let fact = Fact {
+            id: now.timestamp_micros().unsigned_abs(),
+            session: session.to_string(),
+            category: category.to_string(),
+            data: data.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let embedding = self.embed(&fact.embedding_input()).await?;
+        self.store.upsert(&fact, embedding).await?;
+        log::info!(
+            "Remembered fact {} in '{}' store",
+            fact.id,
+            self.store.name()
+        );
+
+        Ok(fact)
+    }
+
+    /// Returns facts relevant to the question, best matching first.
+    ///
+    /// * `session`: conversation session id
+    /// * `question`: question to answer using facts
+    /// * `limit`: maximal number of facts
+    pub async fn recall(
+        &self,
+        session: &str,
+        question: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Fact>> {
+        let embedding = self.embed(question).await?;
+        let facts = self
+            .store
+            .search(session, embedding, limit)
+            .await?
+            .into_iter()
+            .filter(|f| f.score >= RECALL_MIN_SCORE)
+            .map(|f| f.fact)
+            .collect::<Vec<_>>();
+        log::debug!("Recalled {} facts", facts.len());
+
+        Ok(facts)
+    }
+
+    /// Removes the fact best matching the description. Returns removed fact, if any matched.
+    ///
+    /// * `session`: conversation session id
+    /// * `description`: description of the fact to forget
+    pub async fn forget(&self, session: &str, description: &str) -> anyhow::Result<Option<Fact>> {
+        let Some(fact) = self.find(session, description).await? else {
+            return Ok(None);
+        };
+
+        self.store.delete(fact.id).await?;
+        log::info!("Forgot fact {}", fact.id);
+
+        Ok(Some(fact))
+    }
+
+    /// Replaces content of the fact best matching the description. Returns updated fact, if any matched.
+    ///
+    /// * `session`: conversation session id
+    /// * `description`: description of the fact to update
+    /// * `data`: new fact content
+    /// * `category`: new fact category, current one is kept when not provided
+    pub async fn update(
+        &self,
+        session: &str,
+        description: &str,
+        data: &str,
+        category: Option<&str>,
+    ) -> anyhow::Result<Option<Fact>> {
+        let Some(mut fact) = self.find(session, description).await? else {
+            return Ok(None);
+        };
+
+        fact.data = data.to_string();
+        if let Some(category) = category {
+            fact.category = category.to_string();
+        }
+        fact.updated_at = Utc::now();
+
+        let embedding = self.embed(&fact.embedding_input()).await?;
+        self.store.upsert(&fact, embedding).await?;
+        log::info!("Updated fact {}", fact.id);
+
+        Ok(Some(fact))
+    }
+
+    async fn find(&self, session: &str, description: &str) -> anyhow::Result<Option<Fact>> {
+        let embedding = self.embed(description).await?;
+        let fact = self
+            .store
+            .search(session, embedding, 1)
+            .await?
+            .into_iter()
+            .find(|f| f.score >= MATCH_MIN_SCORE)
+            .map(|f| f.fact);
+        Ok(fact)
+    }
+
+    async fn embed(&self, input: &str) -> anyhow::Result<Vec<f32>> {
+        let embedding = utils::embed_text(&self.openai_client, utils::EMBEDDING_MODEL, input)
+            .await?
+            .embedding;
+        Ok(embedding)
+    }
+}
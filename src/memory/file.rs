@@ -0,0 +1,183 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::{Fact, MemoryStore, ScoredFact};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredFact {
+    #[serde(flatten)]
+    fact: Fact,
+    embedding: Vec<f32>,
+}
+
+/// Memory stored in a JSON file, for use without vector database.
+/// All facts are kept in memory and the file is rewritten after every change.
+pub struct FileMemoryStore {
+    path: PathBuf,
+    facts: Mutex<Vec<StoredFact>>,
+}
+
+impl FileMemoryStore {
+    /// Loads facts from the file. Missing file is treated as empty memory.
+    ///
+    /// * `path`: path to the JSON file
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let facts = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Can not parse memory file {}: {e}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            facts: Mutex::new(facts),
+        })
+    }
+
+    /// File is written to a temporary location first, so a crash never leaves it truncated.
+    fn save(&self, facts: &[StoredFact]) -> anyhow::Result<()> {
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(facts)?)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    async fn upsert_fact(&self, fact: &Fact, embedding: Vec<f32>) -> anyhow::Result<()> {
+        let mut facts = self.facts.lock().await;
+        let stored = StoredFact {
+            fact: fact.clone(),
+            embedding,
+        };
+        match facts.iter_mut().find(|f| f.fact.id == fact.id) {
+            Some(existing) => *existing = stored,
+            None => facts.push(stored),
+        }
+        self.save(&facts)
+    }
+
+    async fn search_facts(
+        &self,
+        session: &str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ScoredFact>> {
+        let facts = self.facts.lock().await;
+        let mut scored = facts
+            .iter()
+            .filter(|f| f.fact.session == session)
+            .map(|f| ScoredFact {
+                fact: f.fact.clone(),
+                score: cosine_similarity(&embedding, &f.embedding),
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn delete_fact(&self, id: u64) -> anyhow::Result<()> {
+        let mut facts = self.facts.lock().await;
+        facts.retain(|f| f.fact.id != id);
+        self.save(&facts)
+    }
+}
+
+impl MemoryStore for FileMemoryStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn upsert<'a>(
+        &'a self,
+        fact: &'a Fact,
+        embedding: Vec<f32>,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        self.upsert_fact(fact, embedding).boxed()
+    }
+
+    fn search<'a>(
+        &'a self,
+        session: &'a str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<ScoredFact>>> {
+        self.search_facts(session, embedding, limit).boxed()
+    }
+
+    fn delete(&self, id: u64) -> BoxFuture<'_, anyhow::Result<()>> {
+        self.delete_fact(id).boxed()
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn fact(id: u64, session: &str, data: &str) -> Fact {
+        let now = Utc::now();
+        Fact {
+            id,
+            session: session.into(),
+            category: "test".into(),
+            data: data.into(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_memory_store_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.json");
+
+        let store = FileMemoryStore::open(&path).unwrap();
+        store
+            .upsert(&fact(1, "a", "pizza"), vec![1.0, 0.0])
+            .await
+            .unwrap();
+        store
+            .upsert(&fact(2, "a", "guitar"), vec![0.0, 1.0])
+            .await
+            .unwrap();
+        store
+            .upsert(&fact(3, "a", "pasta"), vec![0.9, 0.1])
+            .await
+            .unwrap();
+        store
+            .upsert(&fact(2, "a", "piano"), vec![0.0, 1.0])
+            .await
+            .unwrap();
+        store
+            .upsert(&fact(4, "b", "drums"), vec![0.0, 1.0])
+            .await
+            .unwrap();
+        store.delete(3).await.unwrap();
+
+        let store = FileMemoryStore::open(&path).unwrap();
+        let found = store.search("a", vec![0.1, 1.0], 5).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].fact.data, "piano");
+        assert!(found[0].score > found[1].score);
+    }
+}
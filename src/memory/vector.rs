@@ -0,0 +1,112 @@
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use qdrant_client::{
+    client::{Payload, QdrantClient},
+    qdrant::{Condition, Filter, PointId, PointStruct, PointsSelector, SearchPoints},
+};
+
+use crate::utils;
+
+use super::{Fact, MemoryStore, ScoredFact};
+
+/// Memory stored in Qdrant collection. Fact ids are used as point ids.
+pub struct QdrantMemoryStore {
+    client: QdrantClient,
+    collection: String,
+}
+
+impl QdrantMemoryStore {
+    /// Creates store, the collection is created when it does not exist.
+    ///
+    /// * `client`: Qdrant client
+    /// * `collection`: name of the collection
+    pub async fn new(client: QdrantClient, collection: &str) -> anyhow::Result<Self> {
+        if !client.collection_exists(collection).await? {
+            log::info!("Qdrant collection '{collection}' does not exists, creating it");
+            utils::qdrant_create_collection(&client, collection).await?;
+        }
+
+        Ok(Self {
+            client,
+            collection: collection.to_string(),
+        })
+    }
+
+    async fn upsert_point(&self, fact: &Fact, embedding: Vec<f32>) -> anyhow::Result<()> {
+        let payload = Payload::try_from(serde_json::to_value(fact)?)
+            .map_err(|e| anyhow!("Can not convert fact to payload: {e}"))?;
+        let point = PointStruct::new(fact.id, embedding, payload);
+        self.client
+            .upsert_points_blocking(&self.collection, None, vec![point], None)
+            .await?;
+        Ok(())
+    }
+
+    async fn search_points(
+        &self,
+        session: &str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ScoredFact>> {
+        let request = SearchPoints {
+            collection_name: self.collection.clone(),
+            vector: embedding,
+            filter: Some(Filter::must([Condition::matches(
+                "session",
+                session.to_string(),
+            )])),
+            limit: limit as u64,
+            with_payload: Some(true.into()),
+            ..Default::default()
+        };
+
+        self.client
+            .search_points(&request)
+            .await?
+            .result
+            .into_iter()
+            .map(|point| {
+                let fact = serde_json::from_value(serde_json::to_value(&point.payload)?)?;
+                Ok(ScoredFact {
+                    fact,
+                    score: point.score,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_point(&self, id: u64) -> anyhow::Result<()> {
+        let selector = PointsSelector::from(vec![PointId::from(id)]);
+        self.client
+            .delete_points_blocking(&self.collection, None, &selector, None)
+            .await?;
+        Ok(())
+    }
+}
+
+impl MemoryStore for QdrantMemoryStore {
+    fn name(&self) -> &'static str {
+        "qdrant"
+    }
+
+    fn upsert<'a>(
+        &'a self,
+        fact: &'a Fact,
+        embedding: Vec<f32>,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        self.upsert_point(fact, embedding).boxed()
+    }
+
+    fn search<'a>(
+        &'a self,
+        session: &'a str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<ScoredFact>>> {
+        self.search_points(session, embedding, limit).boxed()
+    }
+
+    fn delete(&self, id: u64) -> BoxFuture<'_, anyhow::Result<()>> {
+        self.delete_point(id).boxed()
+    }
+}
@@ -0,0 +1,381 @@
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use async_openai::{
+    config::OpenAIConfig,
+    types::{CategoryScore, CreateModerationRequestArgs, TextModerationModel},
+    Client,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumString, Deserialize, Serialize,
+)]
+pub enum ModerationCategory {
+    #[strum(serialize = "hate")]
+    #[serde(rename = "hate")]
+    Hate,
+    #[strum(serialize = "hate/threatening")]
+    #[serde(rename = "hate/threatening")]
+    HateThreatening,
+    #[strum(serialize = "harassment")]
+    #[serde(rename = "harassment")]
+    Harassment,
+    #[strum(serialize = "harassment/threatening")]
+    #[serde(rename = "harassment/threatening")]
+    HarassmentThreatening,
+    #[strum(serialize = "self-harm")]
+    #[serde(rename = "self-harm")]
+    SelfHarm,
+    #[strum(serialize = "self-harm/intent")]
+    #[serde(rename = "self-harm/intent")]
+    SelfHarmIntent,
+    #[strum(serialize = "self-harm/instructions")]
+    #[serde(rename = "self-harm/instructions")]
+    SelfHarmInstructions,
+    #[strum(serialize = "sexual")]
+    #[serde(rename = "sexual")]
+    Sexual,
+    #[strum(serialize = "sexual/minors")]
+    #[serde(rename = "sexual/minors")]
+    SexualMinors,
+    #[strum(serialize = "violence")]
+    #[serde(rename = "violence")]
+    Violence,
+    #[strum(serialize = "violence/graphic")]
+    #[serde(rename = "violence/graphic")]
+    ViolenceGraphic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ModerationBackend {
+    /// Local pre-filter and OpenAI moderation API
+    OpenAI,
+    /// Local pre-filter only, works offline
+    Local,
+}
+
+/// Pre-filter rule flagging inputs matching the pattern.
+#[derive(Debug, Clone, Deserialize)]
+struct RuleDefinition {
+    category: ModerationCategory,
+    pattern: String,
+}
+
+#[derive(Debug)]
+struct Rule {
+    category: ModerationCategory,
+    pattern: Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModerationResult {
+    pub flagged: bool,
+    /// Categories with scores reaching their thresholds
+    pub categories: Vec<ModerationCategory>,
+    pub scores: BTreeMap<ModerationCategory, f32>,
+    /// Input was flagged by the local pre-filter, without asking the API
+    pub prefiltered: bool,
+}
+
+/// Moderation of user inputs with category thresholds.
+/// Inputs are checked by local regex rules first, only the rest is sent to OpenAI moderation API.
+pub struct Moderator {
+    rules: Vec<Rule>,
+    thresholds: BTreeMap<ModerationCategory, f32>,
+    default_threshold: Option<f32>,
+    client: Option<Client<OpenAIConfig>>,
+}
+
+impl Moderator {
+    /// * `client`: OpenAI client, only local rules are used when not provided
+    pub fn new(client: Option<Client<OpenAIConfig>>) -> Self {
+        Self {
+            rules: Vec::new(),
+            thresholds: BTreeMap::new(),
+            default_threshold: None,
+            client,
+        }
+    }
+
+    /// Creates moderator with backend, rules and thresholds from configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let client = match config.moderation_backend {
+            ModerationBackend::OpenAI => Some(Client::with_config(OpenAIConfig::default())),
+            ModerationBackend::Local => None,
+        };
+        let mut moderator = Self::new(client);
+        if config.moderation_rules.exists() {
+            moderator.load_rules(&config.moderation_rules)?;
+        }
+        if let Some(thresholds) = &config.moderation_thresholds {
+            moderator.parse_thresholds(thresholds)?;
+        }
+        if let Some(threshold) = config.moderation_default_threshold {
+            moderator.set_default_threshold(threshold)?;
+        }
+
+        Ok(moderator)
+    }
+
+    /// Adds pre-filter rule.
+    ///
+    /// * `category`: category of matching inputs
+    /// * `pattern`: regular expression, case insensitive
+    pub fn add_rule(&mut self, category: ModerationCategory, pattern: &str) -> anyhow::Result<()> {
+        let pattern = Regex::new(&format!("(?i){pattern}"))
+            .map_err(|e| anyhow!("Invalid moderation rule '{pattern}': {e}"))?;
+        self.rules.push(Rule { category, pattern });
+        Ok(())
+    }
+
+    /// Loads pre-filter rules from JSON file with `category` and `pattern` of each rule.
+    ///
+    /// * `path`: path to the rules file
+    pub fn load_rules(&mut self, path: &Path) -> anyhow::Result<()> {
+        let content = fs::read_to_string(path)?;
+        let rules: Vec<RuleDefinition> = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid moderation rules {}: {e}", path.display()))?;
+        for rule in rules {
+            self.add_rule(rule.category, &rule.pattern)?;
+        }
+        log::debug!("Loaded {} moderation rules", self.rules.len());
+        Ok(())
+    }
+
+    /// Sets category thresholds from definition like `violence=0.5,self-harm=0.2`.
+    ///
+    /// * `definition`: comma separated thresholds
+    pub fn parse_thresholds(&mut self, definition: &str) -> anyhow::Result<()> {
+        for threshold in definition.split(',').filter(|t| !t.trim().is_empty()) {
+            let (category, value) = threshold
+                .split_once('=')
+                .ok_or(anyhow!("Invalid moderation threshold '{threshold}'"))?;
+            let category = ModerationCategory::from_str(category.trim())
+                .map_err(|_| anyhow!("Unknown moderation category '{category}'"))?;
+            let value = value.trim().parse::<f32>()?;
+            if !(0.0..=1.0).contains(&value) {
+                bail!("Moderation threshold of {category} must be between 0 and 1");
+            }
+            self.thresholds.insert(category, value);
+        }
+        Ok(())
+    }
+
+    /// Sets threshold of categories without own one.
+    ///
+    /// * `threshold`: threshold between 0 and 1
+    pub fn set_default_threshold(&mut self, threshold: f32) -> anyhow::Result<()> {
+        if !(0.0..=1.0).contains(&threshold) {
+            bail!("Default moderation threshold must be between 0 and 1");
+        }
+        self.default_threshold = Some(threshold);
+        Ok(())
+    }
+
+    /// Moderates inputs, results are in order of inputs.
+    ///
+    /// * `inputs`: texts to moderate
+    pub async fn moderate(&self, inputs: &[String]) -> anyhow::Result<Vec<ModerationResult>> {
+        let mut results = inputs
+            .iter()
+            .map(|input| self.prefilter(input))
+            .collect::<Vec<_>>();
+
+        let remaining = results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| result.is_none())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let Some(client) = self.client.as_ref().filter(|_| !remaining.is_empty()) else {
+            return Ok(results
+                .into_iter()
+                .map(|r| r.unwrap_or_else(ModerationResult::passed))
+                .collect());
+        };
+
+        let request = CreateModerationRequestArgs::default()
+            .input(
+                remaining
+                    .iter()
+                    .map(|i| inputs[*i].clone())
+                    .collect::<Vec<_>>(),
+            )
+            .model(TextModerationModel::Latest)
+            .build()?;
+        let response = client.moderations().create(request).await?;
+        if response.results.len() != remaining.len() {
+            bail!("Moderation API returned results for different number of inputs");
+        }
+
+        for (index, result) in remaining.into_iter().zip(response.results) {
+            let scores = category_scores(&result.category_scores);
+            let api_flagged = serde_json::to_value(&result.categories)?;
+            let categories = scores
+                .iter()
+                .filter(|(category, score)| match self.threshold(**category) {
+                    Some(threshold) => **score >= threshold,
+                    None => api_flagged[category.to_string()].as_bool() == Some(true),
+                })
+                .map(|(category, _)| *category)
+                .collect::<Vec<_>>();
+            results[index] = Some(ModerationResult {
+                flagged: !categories.is_empty(),
+                categories,
+                scores,
+                prefiltered: false,
+            });
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Moderates single input.
+    ///
+    /// * `input`: text to moderate
+    pub async fn moderate_one(&self, input: &str) -> anyhow::Result<ModerationResult> {
+        self.moderate(&[input.to_string()])
+            .await?
+            .pop()
+            .ok_or(anyhow!("Moderation result not found"))
+    }
+
+    /// Moderates user question of assistant APIs, returns the result when the question
+    /// is flagged and should not be answered.
+    ///
+    /// * `question`: incoming question
+    pub async fn screen(&self, question: &str) -> anyhow::Result<Option<ModerationResult>> {
+        let result = self.moderate_one(question).await?;
+        if !result.flagged {
+            return Ok(None);
+        }
+
+        log::warn!(
+            "Question rejected by moderation: {}",
+            result.flagged_categories()
+        );
+        Ok(Some(result))
+    }
+
+    /// Creates moderator screening questions in assistant APIs, when enabled in configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn screening_from_config(config: &Config) -> anyhow::Result<Option<Self>> {
+        match config.moderation_screen_questions {
+            true => Ok(Some(Self::from_config(config)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Threshold of the category, `None` when the API decision should be used.
+    fn threshold(&self, category: ModerationCategory) -> Option<f32> {
+        self.thresholds
+            .get(&category)
+            .copied()
+            .or(self.default_threshold)
+    }
+
+    fn prefilter(&self, input: &str) -> Option<ModerationResult> {
+        let mut categories = self
+            .rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(input))
+            .map(|rule| rule.category)
+            .collect::<Vec<_>>();
+        if categories.is_empty() {
+            return None;
+        }
+        categories.sort();
+        categories.dedup();
+
+        Some(ModerationResult {
+            flagged: true,
+            scores: categories.iter().map(|c| (*c, 1.0)).collect(),
+            categories,
+            prefiltered: true,
+        })
+    }
+}
+
+impl ModerationResult {
+    /// Comma separated flagged categories.
+    pub fn flagged_categories(&self) -> String {
+        self.categories
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn passed() -> Self {
+        Self {
+            flagged: false,
+            categories: Vec::new(),
+            scores: BTreeMap::new(),
+            prefiltered: false,
+        }
+    }
+}
+
+fn category_scores(scores: &CategoryScore) -> BTreeMap<ModerationCategory, f32> {
+    BTreeMap::from([
+        (ModerationCategory::Hate, scores.hate),
+        (ModerationCategory::HateThreatening, scores.hate_threatening),
+        (ModerationCategory::Harassment, scores.harassment),
+        (
+            ModerationCategory::HarassmentThreatening,
+            scores.harassment_threatening,
+        ),
+        (ModerationCategory::SelfHarm, scores.self_harm),
+        (ModerationCategory::SelfHarmIntent, scores.self_harm_intent),
+        (
+            ModerationCategory::SelfHarmInstructions,
+            scores.self_harm_instructions,
+        ),
+        (ModerationCategory::Sexual, scores.sexual),
+        (ModerationCategory::SexualMinors, scores.sexual_minors),
+        (ModerationCategory::Violence, scores.violence),
+        (ModerationCategory::ViolenceGraphic, scores.violence_graphic),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_prefilter() {
+        let mut moderator = Moderator::new(None);
+        moderator
+            .load_rules(Path::new("moderation_rules.json"))
+            .unwrap();
+        moderator
+            .parse_thresholds("violence=0.4, self-harm=0.2")
+            .unwrap();
+        assert!(moderator.parse_thresholds("violence=2").is_err());
+        assert!(moderator.parse_thresholds("spam=0.5").is_err());
+        assert!(moderator.set_default_threshold(1.5).is_err());
+        moderator.set_default_threshold(0.7).unwrap();
+
+        let inputs = [
+            "Jak upiec sernik?".to_string(),
+            "Zabiję cię, jeśli nie oddasz pieniędzy".to_string(),
+        ];
+        let results = moderator.moderate(&inputs).await.unwrap();
+        assert!(!results[0].flagged);
+        assert!(results[1].flagged);
+        assert!(results[1].prefiltered);
+        assert_eq!(
+            results[1].categories,
+            vec![ModerationCategory::HarassmentThreatening]
+        );
+    }
+}
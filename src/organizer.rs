@@ -0,0 +1,119 @@
+mod calendar;
+mod todo;
+
+use chrono::NaiveDate;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+use calendar::IcsCalendar;
+use todo::{JsonTodoList, MarkdownTodoList};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum TodoBackend {
+    Json,
+    Markdown,
+}
+
+/// Action selected for user query, serialized in the format expected by 'tools' task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "tool")]
+pub enum Action {
+    Calendar { desc: String, date: NaiveDate },
+    ToDo { desc: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TodoItem {
+    pub desc: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// Calendar storing whole-day events.
+pub trait Calendar: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn add_event(&self, summary: &str, date: NaiveDate) -> BoxFuture<'_, anyhow::Result<()>>;
+
+    /// Events sorted by date.
+    fn events(&self) -> BoxFuture<'_, anyhow::Result<Vec<CalendarEvent>>>;
+}
+
+/// List of tasks to do.
+pub trait TodoList: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn add_item(&self, desc: &str) -> BoxFuture<'_, anyhow::Result<()>>;
+
+    fn items(&self) -> BoxFuture<'_, anyhow::Result<Vec<TodoItem>>>;
+}
+
+/// Executes actions selected by LLM against calendar and todo list.
+pub struct Organizer {
+    calendar: Box<dyn Calendar>,
+    todo: Box<dyn TodoList>,
+}
+
+impl Organizer {
+    pub fn new(calendar: Box<dyn Calendar>, todo: Box<dyn TodoList>) -> Self {
+        Self { calendar, todo }
+    }
+
+    /// Creates organizer with calendar and todo list files from configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> Self {
+        let todo: Box<dyn TodoList> = match config.todo_backend {
+            TodoBackend::Json => Box::new(JsonTodoList::new(&config.todo_file)),
+            TodoBackend::Markdown => Box::new(MarkdownTodoList::new(&config.todo_file)),
+        };
+        log::info!("Using '{}' todo list", todo.name());
+
+        Self::new(Box::new(IcsCalendar::new(&config.calendar_file)), todo)
+    }
+
+    /// Adds calendar event or todo list item.
+    ///
+    /// * `action`: action selected for user query
+    pub async fn execute(&self, action: &Action) -> anyhow::Result<()> {
+        match action {
+            Action::Calendar { desc, date } => {
+                log::info!(
+                    "Adding '{desc}' on {date} to {} calendar",
+                    self.calendar.name()
+                );
+                self.calendar.add_event(desc, *date).await
+            }
+            Action::ToDo { desc } => {
+                log::info!("Adding '{desc}' to {} todo list", self.todo.name());
+                self.todo.add_item(desc).await
+            }
+        }
+    }
+
+    /// Calendar events from the date.
+    ///
+    /// * `from`: first day of listed events
+    pub async fn upcoming_events(&self, from: NaiveDate) -> anyhow::Result<Vec<CalendarEvent>> {
+        let mut events = self.calendar.events().await?;
+        events.retain(|e| e.date >= from);
+        Ok(events)
+    }
+
+    /// Todo list items not done yet.
+    pub async fn pending_items(&self) -> anyhow::Result<Vec<TodoItem>> {
+        let mut items = self.todo.items().await?;
+        items.retain(|i| !i.done);
+        Ok(items)
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use chrono::{NaiveDate, Utc};
+use futures::{future::BoxFuture, FutureExt};
+use tokio::sync::Mutex;
+
+use super::{Calendar, CalendarEvent};
+
+const CALENDAR_HEADER: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//ai_devs2//tools//EN\r\n";
+const CALENDAR_FOOTER: &str = "END:VCALENDAR\r\n";
+/// Content lines longer than this many octets are folded (RFC 5545, section 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+const ICS_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Calendar in iCalendar (.ics) file, can be imported to or subscribed by calendar apps.
+/// New events are inserted before the end of the calendar, so events added by other apps are kept.
+pub struct IcsCalendar {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl IcsCalendar {
+    /// Creates calendar in the file. Missing file is created with the first event.
+    ///
+    /// * `path`: path to the .ics file
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> anyhow::Result<String> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(format!("{CALENDAR_HEADER}{CALENDAR_FOOTER}"))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn insert_event(&self, summary: &str, date: NaiveDate) -> anyhow::Result<()> {
+        let _lock = self.lock.lock().await;
+        let mut content = self.read()?;
+
+        let now = Utc::now();
+        let event = [
+            "BEGIN:VEVENT".to_string(),
+            format!(
+                "UID:{}@ai_devs2",
+                now.timestamp_nanos_opt().unwrap_or_default()
+            ),
+            format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART;VALUE=DATE:{}", date.format(ICS_DATE_FORMAT)),
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                date.succ_opt().unwrap_or(date).format(ICS_DATE_FORMAT)
+            ),
+            format!("SUMMARY:{}", escape(summary)),
+            "END:VEVENT".to_string(),
+        ]
+        .iter()
+        .map(|line| fold(line))
+        .collect::<String>();
+
+        let end = content
+            .rfind("END:VCALENDAR")
+            .ok_or(anyhow!("Invalid calendar {}", self.path.display()))?;
+        content.insert_str(end, &event);
+
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    async fn read_events(&self) -> anyhow::Result<Vec<CalendarEvent>> {
+        let _lock = self.lock.lock().await;
+        let mut events = parse_events(&self.read()?);
+        events.sort_by_key(|e| e.date);
+        Ok(events)
+    }
+}
+
+impl Calendar for IcsCalendar {
+    fn name(&self) -> &'static str {
+        "ics"
+    }
+
+    fn add_event(&self, summary: &str, date: NaiveDate) -> BoxFuture<'_, anyhow::Result<()>> {
+        let summary = summary.to_string();
+        async move { self.insert_event(&summary, date).await }.boxed()
+    }
+
+    fn events(&self) -> BoxFuture<'_, anyhow::Result<Vec<CalendarEvent>>> {
+        self.read_events().boxed()
+    }
+}
+
+/// Parses events with summary and start date, other properties and components are ignored.
+fn parse_events(content: &str) -> Vec<CalendarEvent> {
+    // Unfold continuation lines, starting with a space or a tab
+    let content = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut summary = None;
+    let mut date = None;
+    for line in content.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Property parameters, e.g. DTSTART;VALUE=DATE
+        let name = name.split(';').next().unwrap_or_default();
+        match name {
+            "BEGIN" if value == "VEVENT" => {
+                summary = None;
+                date = None;
+            }
+            "SUMMARY" => summary = Some(unescape(value)),
+            // Date-time values start with the date, e.g. 20240105T120000Z
+            "DTSTART" => {
+                date = value
+                    .get(..8)
+                    .and_then(|d| NaiveDate::parse_from_str(d, ICS_DATE_FORMAT).ok())
+            }
+            "END" if value == "VEVENT" => {
+                if let (Some(summary), Some(date)) = (summary.take(), date.take()) {
+                    events.push(CalendarEvent { summary, date });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Folds content line to lines of at most `MAX_LINE_OCTETS`, without splitting UTF-8 characters.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ics_calendar_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calendar.ics");
+        let calendar = IcsCalendar::new(&path);
+        let date = |d| NaiveDate::from_ymd_opt(2024, 4, d).unwrap();
+
+        let long_summary = format!("Spotkanie z Marianem; {}", "żółć, ".repeat(20));
+        calendar.add_event(&long_summary, date(12)).await.unwrap();
+        calendar.add_event("Urodziny", date(3)).await.unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(CALENDAR_HEADER));
+        assert!(content.ends_with(CALENDAR_FOOTER));
+        assert!(content.lines().all(|l| l.len() <= MAX_LINE_OCTETS));
+
+        let events = IcsCalendar::new(&path).events().await.unwrap();
+        assert_eq!(
+            events,
+            vec![
+                CalendarEvent {
+                    summary: "Urodziny".into(),
+                    date: date(3)
+                },
+                CalendarEvent {
+                    summary: long_summary,
+                    date: date(12)
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use tokio::sync::Mutex;
+
+use super::{TodoItem, TodoList};
+
+/// Todo list in a JSON file with array of items.
+pub struct JsonTodoList {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+/// Todo list in a Markdown file with task list items, e.g. `- [ ] buy milk`.
+/// New items are appended to the file, other content is kept untouched.
+pub struct MarkdownTodoList {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+/// Reads the file content, missing file is treated as empty.
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// File is written to a temporary location first, so a crash never leaves it truncated.
+fn write_file(path: &Path, content: &str) -> anyhow::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+impl JsonTodoList {
+    /// * `path`: path to the JSON file
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> anyhow::Result<Vec<TodoItem>> {
+        let content = read_file(&self.path)?;
+        if content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Can not parse todo list {}: {e}", self.path.display()))
+    }
+
+    async fn append(&self, desc: &str) -> anyhow::Result<()> {
+        let _lock = self.lock.lock().await;
+        let mut items = self.read()?;
+        items.push(TodoItem {
+            desc: desc.to_string(),
+            done: false,
+        });
+        write_file(&self.path, &serde_json::to_string_pretty(&items)?)
+    }
+
+    async fn read_items(&self) -> anyhow::Result<Vec<TodoItem>> {
+        let _lock = self.lock.lock().await;
+        self.read()
+    }
+}
+
+impl MarkdownTodoList {
+    /// * `path`: path to the Markdown file
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn append(&self, desc: &str) -> anyhow::Result<()> {
+        let _lock = self.lock.lock().await;
+        let mut content = read_file(&self.path)?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        // Item must fit in a single line
+        let desc = desc.split_whitespace().collect::<Vec<_>>().join(" ");
+        content.push_str(&format!("- [ ] {desc}\n"));
+        write_file(&self.path, &content)
+    }
+
+    async fn read_items(&self) -> anyhow::Result<Vec<TodoItem>> {
+        let _lock = self.lock.lock().await;
+        let content = read_file(&self.path)?;
+        let items = content
+            .lines()
+            .filter_map(|line| {
+                let item = line.trim_start().strip_prefix(['-', '*'])?.trim_start();
+                let (done, desc) = match item.get(..3)? {
+                    "[ ]" => (false, &item[3..]),
+                    "[x]" | "[X]" => (true, &item[3..]),
+                    _ => return None,
+                };
+                Some(TodoItem {
+                    desc: desc.trim().to_string(),
+                    done,
+                })
+            })
+            .collect();
+        Ok(items)
+    }
+}
+
+impl TodoList for JsonTodoList {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn add_item(&self, desc: &str) -> BoxFuture<'_, anyhow::Result<()>> {
+        let desc = desc.to_string();
+        async move { self.append(&desc).await }.boxed()
+    }
+
+    fn items(&self) -> BoxFuture<'_, anyhow::Result<Vec<TodoItem>>> {
+        self.read_items().boxed()
+    }
+}
+
+impl TodoList for MarkdownTodoList {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn add_item(&self, desc: &str) -> BoxFuture<'_, anyhow::Result<()>> {
+        let desc = desc.to_string();
+        async move { self.append(&desc).await }.boxed()
+    }
+
+    fn items(&self) -> BoxFuture<'_, anyhow::Result<Vec<TodoItem>>> {
+        self.read_items().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_markdown_todo_list_keeps_other_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.md");
+        fs::write(&path, "# Zakupy\n\n* [x] chleb\nnotatka").unwrap();
+
+        let todo = MarkdownTodoList::new(&path);
+        todo.add_item("kupić\nmleko").await.unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# Zakupy\n\n* [x] chleb\nnotatka\n- [ ] kupić mleko\n"
+        );
+        assert_eq!(
+            todo.items().await.unwrap(),
+            vec![
+                TodoItem {
+                    desc: "chleb".into(),
+                    done: true
+                },
+                TodoItem {
+                    desc: "kupić mleko".into(),
+                    done: false
+                },
+            ]
+        );
+    }
+}
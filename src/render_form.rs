@@ -1,12 +1,41 @@
-use anyhow::bail;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+mod error;
+mod webhook;
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use clap::{Args, Subcommand};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{Map, Value};
 use url::Url;
 
+use crate::{
+    config::Config,
+    server::{ApiServer, ServerOptions},
+    tunnel,
+};
+
+pub use error::RenderFormError;
+pub use webhook::webhook_api;
+
+/// Interval between checks whether the rendered file is available.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Route of the webhook API started by the render command.
+const WEBHOOK_ROUTE: &str = "/renderform/webhook";
+
 pub enum RenderFormRenderDataField {
     Text(String),
     Source(Url),
+    /// Text or shape color, e.g. `#ff0000`
+    Color(String),
+    /// Shape background color, e.g. `#ff0000`
+    BackgroundColor(String),
+    /// Hides the component
+    Hidden(bool),
 }
 
 pub struct RenderFormRenderDataBuilder(Map<String, Value>);
@@ -21,6 +50,7 @@ pub struct RenderFormRenderRequest {
     pub data: Option<RenderFormRenderData>,
     pub expires: Option<u32>,
     pub file_name: Option<String>,
+    /// When set, RenderForm notifies this URL when the render is finished
     pub webhook_url: Option<Url>,
     pub metadata: Option<Value>,
     pub version: Option<String>,
@@ -29,10 +59,25 @@ pub struct RenderFormRenderRequest {
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderFormRenderResponse {
-    request_id: String,
+    pub request_id: String,
     pub href: Url,
 }
 
+/// Template created in RenderForm editor.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderFormTemplate {
+    pub identifier: String,
+    pub name: String,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Remaining template properties, e.g. components and their default values
+    #[serde(flatten)]
+    pub properties: Map<String, Value>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RenderFormApiError {
@@ -51,20 +96,107 @@ enum RenderFormHeader {
     ApiKey,
 }
 
+#[derive(Debug, Args)]
+pub struct RenderFormArgs {
+    #[command(subcommand)]
+    pub command: RenderFormCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RenderFormCommand {
+    /// list templates
+    Templates {
+        /// Page number, starting from 0
+        #[arg(short, long, default_value_t = 0)]
+        page: u32,
+    },
+
+    /// show template details
+    Template {
+        /// Template identifier
+        identifier: String,
+    },
+
+    /// render image from template
+    Render {
+        /// Template identifier
+        #[arg(short, long)]
+        template: String,
+
+        /// Render data field as `component.property=value`, property is one of:
+        /// text, src, color, backgroundColor, hidden
+        #[arg(short, long = "field")]
+        fields: Vec<String>,
+
+        /// Rendered file name
+        #[arg(long)]
+        file_name: Option<String>,
+
+        /// Wait for RenderForm webhook notification, the webhook is exposed with configured tunnel
+        #[arg(long)]
+        webhook: bool,
+
+        /// Maximal time in seconds to wait for the rendered file
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+
+        /// Save rendered file to the path
+        #[arg(short, long)]
+        download: Option<PathBuf>,
+    },
+}
+
+impl RenderFormRenderDataField {
+    /// Parses field from component property name and value.
+    ///
+    /// * `property`: one of `text`, `src`, `color`, `backgroundColor`, `hidden`
+    /// * `value`: property value
+    pub fn parse(property: &str, value: &str) -> Result<Self, RenderFormError> {
+        let invalid = || RenderFormError::InvalidField(format!("{property}={value}"));
+        let field = match property {
+            "text" => Self::Text(value.to_string()),
+            "src" => Self::Source(value.parse().map_err(|_| invalid())?),
+            "color" => Self::Color(value.to_string()),
+            "backgroundColor" => Self::BackgroundColor(value.to_string()),
+            "hidden" => Self::Hidden(value.parse().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+
+        Ok(field)
+    }
+}
+
 impl RenderFormRenderDataBuilder {
     pub fn new() -> Self {
         Self(Map::new())
     }
 
     pub fn set(mut self, component: &str, field: RenderFormRenderDataField) -> Self {
-        let (key, value) = match field {
-            RenderFormRenderDataField::Text(text) => (format!("{component}.text"), text),
-            RenderFormRenderDataField::Source(src) => (format!("{component}.src"), src.into()),
+        let (property, value) = match field {
+            RenderFormRenderDataField::Text(text) => ("text", Value::String(text)),
+            RenderFormRenderDataField::Source(src) => ("src", Value::String(src.into())),
+            RenderFormRenderDataField::Color(color) => ("color", Value::String(color)),
+            RenderFormRenderDataField::BackgroundColor(color) => {
+                ("backgroundColor", Value::String(color))
+            }
+            RenderFormRenderDataField::Hidden(hidden) => ("hidden", Value::Bool(hidden)),
         };
-        self.0.insert(key, Value::String(value));
+        self.0.insert(format!("{component}.{property}"), value);
         self
     }
 
+    /// Sets field from `component.property=value` definition.
+    ///
+    /// * `definition`: field definition, e.g. `title.text=Hello`
+    pub fn parse_set(self, definition: &str) -> Result<Self, RenderFormError> {
+        let invalid = || RenderFormError::InvalidField(definition.to_string());
+        let (key, value) = definition.split_once('=').ok_or_else(invalid)?;
+        let (component, property) = key.rsplit_once('.').ok_or_else(invalid)?;
+        let field = RenderFormRenderDataField::parse(property, value).map_err(|_| invalid())?;
+
+        Ok(self.set(component, field))
+    }
+
     pub fn build(self) -> RenderFormRenderData {
         RenderFormRenderData(self.0.into())
     }
@@ -80,30 +212,130 @@ impl RenderFormClient {
         }
     }
 
+    /// Renders image from template. Without webhook the file is rendered before the response,
+    /// otherwise the returned URL becomes available when RenderForm calls the webhook.
+    ///
+    /// * `request`: render request
     pub async fn render(
         &self,
         request: RenderFormRenderRequest,
-    ) -> anyhow::Result<RenderFormRenderResponse> {
-        let response = self
+    ) -> Result<RenderFormRenderResponse, RenderFormError> {
+        let request = self
             .client
             .post(format!("{}/render", Self::API_BASE_URL))
+            .json(&request);
+
+        self.send(request).await
+    }
+
+    /// Lists templates of the API key owner.
+    ///
+    /// * `page`: page number, starting from 0
+    pub async fn templates(&self, page: u32) -> Result<Vec<RenderFormTemplate>, RenderFormError> {
+        let request = self
+            .client
+            .get(format!("{}/my-templates", Self::API_BASE_URL))
+            .query(&[("page", page)]);
+
+        self.send(request).await
+    }
+
+    /// Gets template details.
+    ///
+    /// * `identifier`: template identifier
+    pub async fn template(&self, identifier: &str) -> Result<RenderFormTemplate, RenderFormError> {
+        let request = self
+            .client
+            .get(format!("{}/my-templates/{identifier}", Self::API_BASE_URL));
+
+        self.send(request).await
+    }
+
+    /// Polls rendered file URL until the file is available.
+    ///
+    /// * `href`: rendered file URL
+    /// * `timeout`: maximal waiting time
+    pub async fn wait_until_ready(
+        &self,
+        href: &Url,
+        timeout: Duration,
+    ) -> Result<(), RenderFormError> {
+        let started = Instant::now();
+        loop {
+            let response = self.client.head(href.clone()).send().await?;
+            match response.status() {
+                status if status.is_success() => return Ok(()),
+                StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => {
+                    log::debug!("Rendered file {href} not available yet")
+                }
+                _ => return Err(Self::api_error(response).await),
+            }
+
+            if started.elapsed() + POLL_INTERVAL > timeout {
+                return Err(RenderFormError::NotReady {
+                    href: href.clone(),
+                    waited: started.elapsed(),
+                });
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Downloads rendered file and returns its size in bytes.
+    ///
+    /// * `href`: rendered file URL
+    /// * `path`: destination path
+    pub async fn download(&self, href: &Url, path: &Path) -> Result<usize, RenderFormError> {
+        let response = self.client.get(href.clone()).send().await?;
+        if !response.status().is_success() {
+            return Err(Self::api_error(response).await);
+        }
+
+        let bytes = response.bytes().await?;
+        std::fs::write(path, &bytes)?;
+
+        Ok(bytes.len())
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, RenderFormError> {
+        let response = request
             .header(RenderFormHeader::ApiKey.as_str(), &self.api_key)
-            .json(&request)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let api_error = response.json::<RenderFormApiError>().await?;
-            bail!(
-                "RenderForm API error: {} [{}] {:?}",
-                api_error.msg,
-                api_error.status,
-                api_error.errors
-            )
+            return Err(Self::api_error(response).await);
         }
 
-        let response = response.json::<RenderFormRenderResponse>().await?;
-        Ok(response)
+        Ok(response.json::<T>().await?)
+    }
+
+    /// Converts error response to API error. Bodies which are not RenderForm errors are used as a message.
+    async fn api_error(response: Response) -> RenderFormError {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+
+        match serde_json::from_str::<RenderFormApiError>(&body) {
+            Ok(api_error) => RenderFormError::Api {
+                status: status.as_u16(),
+                message: api_error.msg,
+                errors: api_error.errors,
+            },
+            Err(_) => RenderFormError::Api {
+                status: status.as_u16(),
+                message: match body.trim() {
+                    "" => status
+                        .canonical_reason()
+                        .unwrap_or("Unknown error")
+                        .to_string(),
+                    body => body.to_string(),
+                },
+                errors: Vec::new(),
+            },
+        }
     }
 }
 
@@ -114,3 +346,175 @@ impl RenderFormHeader {
         }
     }
 }
+
+/// Runs RenderForm command: lists templates, shows template or renders image.
+///
+/// * `config`: App configuration
+/// * `args`: render-form command arguments
+pub async fn run(config: &Config, args: RenderFormArgs) -> anyhow::Result<()> {
+    let api_key = config
+        .render_form_api_key
+        .as_ref()
+        .ok_or(anyhow!("RenderForm API key not found in configuration"))?;
+    let client = RenderFormClient::new(api_key);
+
+    let result = execute(config, &client, args.command).await;
+    if let Some(err) = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<RenderFormError>())
+    {
+        if err.is_unauthorized() {
+            log::error!("Check RENDER_FORM_API_KEY in configuration");
+        }
+    }
+
+    result
+}
+
+async fn execute(
+    config: &Config,
+    client: &RenderFormClient,
+    command: RenderFormCommand,
+) -> anyhow::Result<()> {
+    match command {
+        RenderFormCommand::Templates { page } => {
+            for template in client.templates(page).await? {
+                println!("{}\t{}", template.identifier, template.name);
+            }
+        }
+        RenderFormCommand::Template { identifier } => {
+            let template = client.template(&identifier).await?;
+            println!("{}", serde_json::to_string_pretty(&template)?);
+        }
+        RenderFormCommand::Render {
+            template,
+            fields,
+            file_name,
+            webhook,
+            timeout,
+            download,
+        } => {
+            let mut data = RenderFormRenderDataBuilder::new();
+            for field in &fields {
+                data = data.parse_set(field)?;
+            }
+            let request = RenderFormRenderRequest {
+                template,
+                data: Some(data.build()),
+                file_name,
+                ..Default::default()
+            };
+            let timeout = Duration::from_secs(timeout);
+
+            let href = match webhook {
+                true => render_with_webhook(config, client, request, timeout).await?,
+                false => render_with_polling(client, request, timeout).await?,
+            };
+            println!("{href}");
+
+            if let Some(path) = download {
+                let size = client.download(&href, &path).await?;
+                log::info!("Saved {size} bytes to {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn render_with_polling(
+    client: &RenderFormClient,
+    request: RenderFormRenderRequest,
+    timeout: Duration,
+) -> anyhow::Result<Url> {
+    let response = client.render(request).await?;
+    log::info!("Render request {} accepted", response.request_id);
+
+    client.wait_until_ready(&response.href, timeout).await?;
+    Ok(response.href)
+}
+
+/// Starts webhook API exposed with configured tunnel and waits for the render notification.
+async fn render_with_webhook(
+    config: &Config,
+    client: &RenderFormClient,
+    mut request: RenderFormRenderRequest,
+    timeout: Duration,
+) -> anyhow::Result<Url> {
+    let listen_address = config
+        .api_listen_address
+        .as_ref()
+        .ok_or(anyhow!("API listen address not specified"))?;
+    let mut tunnel = tunnel::from_config(config)?;
+
+    let (api, mut events) = webhook_api(WEBHOOK_ROUTE);
+    let mut server = ApiServer::new(
+        (),
+        ServerOptions {
+            shutdown_after: None,
+            ..ServerOptions::from(config)
+        },
+    );
+    server.mount(WEBHOOK_ROUTE, api);
+    let server = server.start(listen_address).await?;
+
+    let result = async {
+        let mut webhook_url = tunnel.open(listen_address).await?;
+        webhook_url.set_path(WEBHOOK_ROUTE);
+        request.webhook_url = Some(webhook_url);
+
+        let response = client.render(request).await?;
+        log::info!(
+            "Render request {} accepted, waiting for webhook",
+            response.request_id
+        );
+
+        loop {
+            let event = tokio::time::timeout(timeout, events.recv())
+                .await
+                .map_err(|_| RenderFormError::NotReady {
+                    href: response.href.clone(),
+                    waited: timeout,
+                })?
+                .ok_or(anyhow!("Webhook API stopped"))?;
+            if event.request_id == response.request_id {
+                return Ok(event.href);
+            }
+        }
+    }
+    .await;
+
+    server.stop();
+    tunnel.close().await?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_render_data() {
+        let data = RenderFormRenderDataBuilder::new()
+            .set("title", RenderFormRenderDataField::Text("Hello".into()))
+            .parse_set("logo.hidden=true")
+            .unwrap()
+            .parse_set("box.backgroundColor=#ff0000")
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(data).unwrap(),
+            serde_json::json!({
+                "title.text": "Hello",
+                "logo.hidden": true,
+                "box.backgroundColor": "#ff0000",
+            })
+        );
+        assert!(matches!(
+            RenderFormRenderDataBuilder::new().parse_set("title.size=10"),
+            Err(RenderFormError::InvalidField(_))
+        ));
+    }
+}
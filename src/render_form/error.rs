@@ -0,0 +1,86 @@
+use std::{fmt, time::Duration};
+
+use url::Url;
+
+/// Errors returned by RenderForm client.
+#[derive(Debug)]
+pub enum RenderFormError {
+    /// API responded with error status
+    Api {
+        status: u16,
+        message: String,
+        errors: Vec<String>,
+    },
+    /// Request failed before API responded, or response body is invalid
+    Request(reqwest::Error),
+    /// Render data field can not be parsed
+    InvalidField(String),
+    /// Rendered file not available in expected time
+    NotReady { href: Url, waited: Duration },
+    /// Downloaded file can not be saved
+    Io(std::io::Error),
+}
+
+impl RenderFormError {
+    /// API key is invalid or does not allow the operation.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(
+            self,
+            Self::Api {
+                status: 401 | 403,
+                ..
+            }
+        )
+    }
+}
+
+impl fmt::Display for RenderFormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Api {
+                status,
+                message,
+                errors,
+            } if errors.is_empty() => write!(f, "RenderForm API error: {message} [{status}]"),
+            Self::Api {
+                status,
+                message,
+                errors,
+            } => write!(
+                f,
+                "RenderForm API error: {message} [{status}]: {}",
+                errors.join(", ")
+            ),
+            Self::Request(err) => write!(f, "RenderForm request failed: {err}"),
+            Self::InvalidField(field) => write!(f, "Invalid render data field: {field}"),
+            Self::NotReady { href, waited } => write!(
+                f,
+                "Rendered file {href} not available after {} s",
+                waited.as_secs()
+            ),
+            Self::Io(err) => write!(f, "Can not save rendered file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderFormError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for RenderFormError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Request(err)
+    }
+}
+
+impl From<std::io::Error> for RenderFormError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
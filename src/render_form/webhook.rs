@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use tide::{Request, Response, StatusCode};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use url::Url;
+
+/// Notification sent by RenderForm to the request `webhook_url` when the render is finished.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderFormWebhookEvent {
+    pub request_id: String,
+    pub href: Url,
+}
+
+/// Creates API receiving RenderForm webhook notifications. Received events are sent to the returned channel.
+///
+/// * `route`: route handling webhook requests
+pub fn webhook_api(
+    route: &str,
+) -> (
+    tide::Server<UnboundedSender<RenderFormWebhookEvent>>,
+    UnboundedReceiver<RenderFormWebhookEvent>,
+) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut app = tide::with_state(sender);
+    app.at(route).post(webhook_handler);
+
+    (app, receiver)
+}
+
+async fn webhook_handler(
+    mut request: Request<UnboundedSender<RenderFormWebhookEvent>>,
+) -> tide::Result {
+    let event: RenderFormWebhookEvent = request.body_json().await?;
+    log::info!(
+        "RenderForm webhook: request {} rendered to {}",
+        event.request_id,
+        event.href
+    );
+
+    if request.state().send(event).is_err() {
+        log::warn!("RenderForm webhook event dropped, nobody is waiting for it");
+    }
+
+    Ok(Response::new(StatusCode::Ok))
+}
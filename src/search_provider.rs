@@ -0,0 +1,85 @@
+mod brave;
+mod offline;
+mod searxng;
+
+use anyhow::{anyhow, bail};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+use url::Url;
+
+use crate::{brave_search::BraveSearchClient, config::Config, fetcher::Fetcher};
+
+pub use brave::BraveSearchProvider;
+pub use offline::OfflineSearchProvider;
+pub use searxng::SearxngSearchProvider;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum SearchBackend {
+    Brave,
+    Searxng,
+    Offline,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SearchHit {
+    pub title: String,
+    pub url: Url,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Web search engine used by tasks.
+pub trait SearchProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Searches the web and returns at most `limit` hits, best matching first.
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<SearchHit>>>;
+}
+
+/// Creates search provider selected in configuration.
+/// When backend is not set explicitly, Brave is used if its API key is present, SearXNG otherwise.
+///
+/// * `config`: App configuration
+pub fn from_config(config: &Config) -> anyhow::Result<Box<dyn SearchProvider>> {
+    let backend = match config.search_provider {
+        Some(backend) => backend,
+        None if config.brave_search_api_key.is_some() => SearchBackend::Brave,
+        None if config.searxng_url.is_some() => SearchBackend::Searxng,
+        None => bail!("Search provider not configured, set BRAVE_SEARCH_API_KEY or SEARXNG_URL"),
+    };
+    log::info!("Using '{backend}' search provider");
+
+    let provider: Box<dyn SearchProvider> = match backend {
+        SearchBackend::Brave => Box::new(BraveSearchProvider::new(
+            BraveSearchClient::from_config(config)?,
+            config,
+        )),
+        SearchBackend::Searxng => {
+            let url = config
+                .searxng_url
+                .as_ref()
+                .ok_or(anyhow!("SearXNG URL not found in configuration"))?;
+            let fetcher = Fetcher::from_config(config)?;
+            Box::new(SearxngSearchProvider::new(
+                url.clone(),
+                fetcher,
+                config.brave_search_lang.clone(),
+            ))
+        }
+        SearchBackend::Offline => {
+            let path = config
+                .search_fixtures_path
+                .as_ref()
+                .ok_or(anyhow!("Search fixtures path not found in configuration"))?;
+            Box::new(OfflineSearchProvider::from_file(path)?)
+        }
+    };
+
+    Ok(provider)
+}
@@ -0,0 +1,65 @@
+use futures::{future::BoxFuture, FutureExt};
+
+use super::{SearchHit, SearchProvider};
+use crate::{
+    brave_search::{BraveSearchClient, BraveSearchParams},
+    config::Config,
+};
+
+pub struct BraveSearchProvider {
+    client: BraveSearchClient,
+    params: BraveSearchParams,
+}
+
+impl BraveSearchProvider {
+    /// Brave API returns at most 20 web results per page.
+    const MAX_COUNT: usize = 20;
+
+    pub fn new(client: BraveSearchClient, config: &Config) -> Self {
+        Self {
+            client,
+            params: BraveSearchParams::from(config),
+        }
+    }
+
+    async fn search_web(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+        let params = BraveSearchParams {
+            count: Some(limit.min(Self::MAX_COUNT) as u8),
+            ..self.params.clone()
+        };
+
+        let result = self.client.search(query, &params).await?;
+        if result.rate_limit.is_exhausted() {
+            log::warn!("Brave Search rate limit exhausted: {:?}", result.rate_limit);
+        }
+
+        let hits = result
+            .response
+            .web
+            .results
+            .into_iter()
+            .take(limit)
+            .map(|r| SearchHit {
+                title: r.title,
+                url: r.url,
+                description: r.description,
+            })
+            .collect();
+
+        Ok(hits)
+    }
+}
+
+impl SearchProvider for BraveSearchProvider {
+    fn name(&self) -> &'static str {
+        "brave"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<SearchHit>>> {
+        self.search_web(query, limit).boxed()
+    }
+}
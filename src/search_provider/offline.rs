@@ -0,0 +1,87 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use serde::Deserialize;
+
+use super::{SearchHit, SearchProvider};
+
+#[derive(Debug, Deserialize)]
+pub struct FixtureEntry {
+    pub query: String,
+    pub results: Vec<SearchHit>,
+}
+
+/// Search provider which answers from recorded fixtures, used for tests and offline runs.
+/// Query is matched with the fixture entry sharing most words with it.
+pub struct OfflineSearchProvider {
+    entries: Vec<FixtureEntry>,
+}
+
+impl OfflineSearchProvider {
+    pub fn new(entries: Vec<FixtureEntry>) -> Self {
+        Self { entries }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let entries = serde_json::from_str(&content)?;
+        Ok(Self::new(entries))
+    }
+
+    fn find(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query_words = words(query);
+
+        self.entries
+            .iter()
+            .map(|e| (words(&e.query).intersection(&query_words).count(), e))
+            .filter(|(common, _)| *common > 0)
+            .max_by_key(|(common, _)| *common)
+            .map(|(_, e)| e.results.iter().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl SearchProvider for OfflineSearchProvider {
+    fn name(&self) -> &'static str {
+        "offline"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<SearchHit>>> {
+        future::ready(Ok(self.find(query, limit))).boxed()
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_offline_search_matches_fixture() {
+        let provider =
+            OfflineSearchProvider::from_file("fixtures/search.json").expect("fixture file");
+
+        let hits = provider
+            .search("Rust programming language homepage", 1)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].url.as_str(), "https://www.rust-lang.org/");
+
+        let hits = provider.search("zzz", 5).await.unwrap();
+        assert!(hits.is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+use url::Url;
+
+use super::{SearchHit, SearchProvider};
+use crate::fetcher::Fetcher;
+
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    url: Url,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+/// SearXNG metasearch engine instance, e.g. started locally with docker.
+/// JSON output format has to be enabled in the instance settings.
+pub struct SearxngSearchProvider {
+    base_url: Url,
+    fetcher: Fetcher,
+    language: Option<String>,
+}
+
+impl SearxngSearchProvider {
+    /// * `base_url`: instance URL, may contain a path prefix, e.g. `http://localhost/searxng`
+    /// * `fetcher`: HTTP client
+    /// * `language`: language of results
+    pub fn new(mut base_url: Url, fetcher: Fetcher, language: Option<String>) -> Self {
+        // Without trailing slash the last path segment would be replaced when joining
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        Self {
+            base_url,
+            fetcher,
+            language,
+        }
+    }
+
+    fn search_url(&self, query: &str) -> anyhow::Result<Url> {
+        let mut url = self.base_url.join("search")?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("format", "json");
+        if let Some(language) = &self.language {
+            url.query_pairs_mut().append_pair("language", language);
+        }
+        Ok(url)
+    }
+
+    async fn search_web(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+        let url = self.search_url(query)?;
+        let response = self.fetcher.get_json::<SearxngResponse>(url).await?;
+        let hits = response
+            .results
+            .into_iter()
+            .take(limit)
+            .map(|r| SearchHit {
+                title: r.title,
+                url: r.url,
+                description: r.content,
+            })
+            .collect();
+
+        Ok(hits)
+    }
+}
+
+impl SearchProvider for SearxngSearchProvider {
+    fn name(&self) -> &'static str {
+        "searxng"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<SearchHit>>> {
+        self.search_web(query, limit).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fetcher::FetcherOptions;
+
+    use super::*;
+
+    #[test]
+    fn test_search_url_keeps_base_path() {
+        let provider = |base_url: &str| {
+            let fetcher = Fetcher::new(FetcherOptions::default()).unwrap();
+            SearxngSearchProvider::new(Url::parse(base_url).unwrap(), fetcher, None)
+        };
+
+        for base_url in ["http://localhost/searxng", "http://localhost/searxng/"] {
+            assert_eq!(
+                provider(base_url).search_url("rysy").unwrap().as_str(),
+                "http://localhost/searxng/search?q=rysy&format=json"
+            );
+        }
+        assert_eq!(
+            provider("http://localhost:8080")
+                .search_url("rysy")
+                .unwrap()
+                .as_str(),
+            "http://localhost:8080/search?q=rysy&format=json"
+        );
+    }
+}
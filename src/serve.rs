@@ -0,0 +1,261 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tide::StatusCode;
+
+use crate::{
+    config::Config,
+    server::{ApiError, ApiServer, ServerOptions},
+    session,
+    tasks::{
+        google, ownapi,
+        ownapipro::{self, OwnapiProAssistant},
+    },
+};
+
+/// Model name reported by the OpenAI-compatible API.
+const MODEL_ID: &str = "aidevs-assistant";
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on [default: API_LISTEN_ADDRESS]
+    #[arg(short, long)]
+    pub listen: Option<String>,
+
+    /// Route of the simple question answering API
+    #[arg(long, default_value = "/ownapi")]
+    pub ownapi_route: String,
+
+    /// Route of the API remembering facts and answering questions
+    #[arg(long, default_value = "/ownapipro")]
+    pub ownapipro_route: String,
+
+    /// Route of the search API
+    #[arg(long, default_value = "/search")]
+    pub search_route: String,
+
+    /// Prefix of the OpenAI-compatible API
+    #[arg(long, default_value = "/v1")]
+    pub openai_route: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionMessage {
+    role: String,
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionRequest {
+    model: Option<String>,
+    messages: Vec<ChatCompletionMessage>,
+    /// End user id, used as session id when the session header is missing
+    user: Option<String>,
+    #[serde(default)]
+    stream: bool,
+}
+
+/// Runs the assistant APIs permanently, without fetching task token or posting answers.
+///
+/// * `config`: App configuration
+/// * `args`: serve command arguments
+pub async fn run(config: &Config, args: ServeArgs) -> anyhow::Result<()> {
+    let listen_address = args
+        .listen
+        .as_ref()
+        .or(config.api_listen_address.as_ref())
+        .ok_or(anyhow!("API listen address not specified"))?;
+
+    let assistant = OwnapiProAssistant::new(config).await?;
+    let mut server = ApiServer::new(
+        (),
+        ServerOptions {
+            shutdown_after: None,
+            ..ServerOptions::from(config)
+        },
+    );
+
+    server.mount(&args.ownapi_route, ownapi::api(config, &args.ownapi_route)?);
+    server.mount(
+        &args.ownapipro_route,
+        ownapipro::api(assistant.clone(), &args.ownapipro_route),
+    );
+    match google::api(config, &args.search_route) {
+        Ok(api) => server.mount(&args.search_route, api),
+        Err(err) => log::warn!("Search API disabled: {err}"),
+    }
+
+    let openai_route = args.openai_route.trim_end_matches('/');
+    let completions_route = format!("{openai_route}/chat/completions");
+    let models_route = format!("{openai_route}/models");
+    let openai_api = openai_api(assistant, &completions_route, &models_route);
+    server.mount(&completions_route, openai_api.clone());
+    server.mount(&models_route, openai_api);
+
+    server.start(listen_address).await?.wait().await
+}
+
+fn openai_api(
+    assistant: OwnapiProAssistant,
+    completions_route: &str,
+    models_route: &str,
+) -> tide::Server<OwnapiProAssistant> {
+    let mut app = tide::with_state(assistant);
+    app.at(completions_route).post(chat_completions_handler);
+    app.at(models_route).get(models_handler);
+    app
+}
+
+impl ChatCompletionRequest {
+    /// The last user message is the question, earlier messages are covered by session history.
+    fn question(&self) -> Result<String, ApiError> {
+        if self.stream {
+            return Err(ApiError::bad_request(
+                "Streaming responses are not supported",
+            ));
+        }
+
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| message_text(&m.content))
+            .filter(|q| !q.is_empty())
+            .ok_or(ApiError::bad_request(
+                "Request does not contain user message",
+            ))
+    }
+
+    /// Chat completion object with the reply as the only choice.
+    ///
+    /// * `reply`: assistant reply
+    /// * `created`: response time
+    fn response(&self, reply: &str, created: DateTime<Utc>) -> Value {
+        json!({
+            "id": format!("chatcmpl-{}", created.timestamp_nanos_opt().unwrap_or_default()),
+            "object": "chat.completion",
+            "created": created.timestamp(),
+            "model": self.model.as_deref().unwrap_or(MODEL_ID),
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": reply },
+                "finish_reason": "stop",
+            }],
+            "usage": { "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 },
+        })
+    }
+}
+
+async fn chat_completions_handler(mut request: tide::Request<OwnapiProAssistant>) -> tide::Result {
+    let chat_request: ChatCompletionRequest = request.body_json().await?;
+    let question = chat_request.question()?;
+    let session_id = session::session_id(&request, chat_request.user.as_deref());
+    log::debug!("Received chat question in session '{session_id}': {question}");
+
+    let reply = request.state().ask(&session_id, &question).await?;
+    let response = chat_request.response(&reply, Utc::now());
+
+    let mut response_body = tide::Response::new(StatusCode::Ok);
+    response_body.set_body(tide::Body::from_json(&response)?);
+    Ok(response_body)
+}
+
+async fn models_handler(_request: tide::Request<OwnapiProAssistant>) -> tide::Result {
+    let mut response_body = tide::Response::new(StatusCode::Ok);
+    response_body.set_body(tide::Body::from_json(&models_response())?);
+    Ok(response_body)
+}
+
+/// List with the only model served by the assistant.
+fn models_response() -> Value {
+    json!({
+        "object": "list",
+        "data": [{ "id": MODEL_ID, "object": "model", "created": 0, "owned_by": "aidevs" }],
+    })
+}
+
+/// Message content is either a string or a list of content parts.
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.trim().to_string(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn request(body: Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_chat_completion_question() {
+        let chat_request = request(json!({
+            "messages": [
+                {"role": "system", "content": "You are helpful"},
+                {"role": "user", "content": "Where do I live?"},
+                {"role": "assistant", "content": "I do not know"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "I live in Kraków."},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}},
+                    {"type": "text", "text": "Where do I live?"}
+                ]},
+                {"role": "assistant", "content": null}
+            ]
+        }));
+        assert_eq!(
+            chat_request.question().unwrap(),
+            "I live in Kraków.\nWhere do I live?"
+        );
+
+        let no_user = request(json!({"messages": [{"role": "system", "content": "Hi"}]}));
+        assert_eq!(
+            no_user.question().unwrap_err().status(),
+            StatusCode::BadRequest
+        );
+        let streaming = request(json!({
+            "stream": true,
+            "messages": [{"role": "user", "content": "Hi"}]
+        }));
+        assert!(streaming.question().is_err());
+    }
+
+    #[test]
+    fn test_chat_completion_response() {
+        let created = Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        let chat_request = request(json!({"messages": []}));
+        assert_eq!(
+            chat_request.response("Kraków", created),
+            json!({
+                "id": "chatcmpl-1706702400000000000",
+                "object": "chat.completion",
+                "created": 1706702400,
+                "model": MODEL_ID,
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Kraków"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0}
+            })
+        );
+
+        let chat_request = request(json!({"model": "gpt-4", "messages": []}));
+        assert_eq!(chat_request.response("", created)["model"], "gpt-4");
+        assert_eq!(models_response()["data"][0]["id"], MODEL_ID);
+    }
+}
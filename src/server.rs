@@ -0,0 +1,324 @@
+mod error;
+
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::AsyncReadExt;
+use serde_json::json;
+use tide::{http::headers, listener::Listener, Middleware, Next, Request};
+use tokio::{signal, sync::Notify, task::JoinHandle, time::sleep};
+
+use crate::{aidevs, config::Config, tunnel};
+
+pub use error::ApiError;
+use error::ErrorResponseMiddleware;
+
+/// Time given to in-flight responses before the server is stopped.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// Stop the server after handling this many requests
+    pub shutdown_after: Option<usize>,
+    /// Maximal size of request body in bytes
+    pub max_body_size: usize,
+    /// Time after which request handling is aborted with 504 status
+    pub request_timeout: Option<Duration>,
+}
+
+/// HTTP server for tasks which require exposing an API to the AI_Devs checker.
+pub struct ApiServer<State> {
+    app: tide::Server<State>,
+    shutdown: Arc<Notify>,
+}
+
+pub struct RunningServer {
+    accept: JoinHandle<std::io::Result<()>>,
+    shutdown: Arc<Notify>,
+}
+
+struct RequestLogMiddleware {
+    next_id: AtomicU64,
+}
+
+struct BodyLimitMiddleware {
+    limit: usize,
+}
+
+struct DeadlineMiddleware {
+    timeout: Duration,
+}
+
+struct RequestLimitMiddleware {
+    limit: usize,
+    handled: AtomicUsize,
+    shutdown: Arc<Notify>,
+}
+
+struct ShutdownOnSuccessMiddleware {
+    shutdown: Arc<Notify>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            shutdown_after: None,
+            max_body_size: 64 * 1024,
+            request_timeout: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl From<&Config> for ServerOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            shutdown_after: config.api_shutdown_after,
+            max_body_size: config.api_max_body_size,
+            request_timeout: Some(Duration::from_secs(config.api_request_timeout_secs))
+                .filter(|t| !t.is_zero()),
+        }
+    }
+}
+
+impl<State: Clone + Send + Sync + 'static> ApiServer<State> {
+    pub fn new(state: State, options: ServerOptions) -> Self {
+        let shutdown = Arc::new(Notify::new());
+
+        let mut app = tide::with_state(state);
+        app.with(RequestLogMiddleware {
+            next_id: AtomicU64::new(1),
+        });
+        app.with(ErrorResponseMiddleware);
+        app.with(BodyLimitMiddleware {
+            limit: options.max_body_size,
+        });
+        if let Some(timeout) = options.request_timeout {
+            app.with(DeadlineMiddleware { timeout });
+        }
+        if let Some(limit) = options.shutdown_after {
+            app.with(RequestLimitMiddleware {
+                limit,
+                handled: AtomicUsize::new(0),
+                shutdown: shutdown.clone(),
+            });
+        }
+
+        Self { app, shutdown }
+    }
+
+    /// Mounts API with its own state at the path. Routes of the mounted API have to contain the full path.
+    ///
+    /// * `path`: route handled by the mounted API
+    /// * `api`: API server
+    pub fn mount<ApiState: Clone + Send + Sync + 'static>(
+        &mut self,
+        path: &str,
+        api: tide::Server<ApiState>,
+    ) {
+        self.app.at(path).all(api);
+    }
+
+    /// Serves the file, e.g. rendered image, and shuts the server down once it is downloaded.
+    ///
+    /// * `path`: route of the file
+    /// * `file`: existing file
+    pub fn serve_file_once(&mut self, path: &str, file: &Path) -> anyhow::Result<()> {
+        self.app
+            .at(path)
+            .with(ShutdownOnSuccessMiddleware {
+                shutdown: self.shutdown.clone(),
+            })
+            .serve_file(file)
+            .map_err(|e| anyhow!("Can not serve {}: {e}", file.display()))
+    }
+
+    /// Starts the server, sends its public URL as the task answer and handles requests until shutdown.
+    ///
+    /// * `config`: App configuration
+    /// * `token`: Task token
+    /// * `endpoint`: path of the endpoint which should be called by the task checker
+    pub async fn run_task(
+        self,
+        config: &Config,
+        token: &str,
+        endpoint: &str,
+    ) -> anyhow::Result<()> {
+        let listen_address = config
+            .api_listen_address
+            .as_ref()
+            .ok_or(anyhow!("API listen address not specified"))?;
+        let mut tunnel = tunnel::from_config(config)?;
+
+        let server = self.start(listen_address).await?;
+
+        let mut public_url = match tunnel.open(listen_address).await {
+            Ok(url) => url,
+            Err(err) => {
+                server.stop();
+                return Err(err);
+            }
+        };
+        public_url.set_path(endpoint);
+        log::info!(
+            "API public endpoint ({} tunnel): {public_url}",
+            tunnel.name()
+        );
+
+        let payload = json!({ "answer" : public_url});
+        let answer_response = aidevs::post_answer(config, token, &payload).await;
+        let result = match answer_response {
+            Ok(r) if r.code == 0 => server.wait().await,
+            Ok(r) => {
+                server.stop();
+                Err(anyhow!("Post answer failed: [{}] {}", r.code, r.msg))
+            }
+            Err(err) => {
+                server.stop();
+                Err(err)
+            }
+        };
+
+        tunnel.close().await?;
+        result
+    }
+
+    /// Binds the listen address and accepts connections in background.
+    /// The server is ready to handle requests when this function returns.
+    ///
+    /// * `listen_address`: address to listen on, e.g. `localhost:8080`
+    pub async fn start(self, listen_address: &str) -> anyhow::Result<RunningServer> {
+        let mut listener = self.app.bind(listen_address.to_string()).await?;
+        for info in listener.info() {
+            log::info!("Server listening on {info}");
+        }
+
+        let accept = tokio::spawn(async move { listener.accept().await });
+
+        Ok(RunningServer {
+            accept,
+            shutdown: self.shutdown,
+        })
+    }
+}
+
+impl RunningServer {
+    /// Waits until the server fails, Ctrl-C is pressed or shutdown is requested,
+    /// e.g. when the request limit is reached.
+    pub async fn wait(mut self) -> anyhow::Result<()> {
+        tokio::select! {
+            result = &mut self.accept => {
+                result??;
+                return Ok(());
+            }
+            _ = signal::ctrl_c() => log::info!("Ctrl-C received, shutting down"),
+            _ = self.shutdown.notified() => log::info!("Shutdown requested, shutting down"),
+        }
+
+        sleep(SHUTDOWN_GRACE).await;
+        self.stop();
+        Ok(())
+    }
+
+    pub fn stop(self) {
+        self.accept.abort();
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for RequestLogMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let method = request.method();
+        let path = request.url().path().to_string();
+        let peer = request.peer_addr().unwrap_or("-").to_string();
+        let started = Instant::now();
+
+        log::debug!("request_id={id} method={method} path={path} peer={peer} started");
+        let response = next.run(request).await;
+        let status = response.status();
+        let duration_ms = started.elapsed().as_millis();
+
+        match response.error() {
+            Some(err) => log::error!(
+                "request_id={id} method={method} path={path} status={status} duration_ms={duration_ms} error=\"{err}\""
+            ),
+            None => log::info!(
+                "request_id={id} method={method} path={path} status={status} duration_ms={duration_ms}"
+            ),
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for BodyLimitMiddleware {
+    async fn handle(&self, mut request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let declared_length = request
+            .header(headers::CONTENT_LENGTH)
+            .and_then(|h| h.last().as_str().parse::<usize>().ok());
+        if declared_length.is_some_and(|l| l > self.limit) {
+            return Err(ApiError::PayloadTooLarge { limit: self.limit }.into());
+        }
+
+        // Chunked bodies do not declare length, so the body is read up to the limit.
+        let mut body = Vec::new();
+        request
+            .take_body()
+            .take(self.limit as u64 + 1)
+            .read_to_end(&mut body)
+            .await?;
+        if body.len() > self.limit {
+            return Err(ApiError::PayloadTooLarge { limit: self.limit }.into());
+        }
+        request.set_body(body);
+
+        Ok(next.run(request).await)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for DeadlineMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        match async_std::future::timeout(self.timeout, next.run(request)).await {
+            Ok(response) => Ok(response),
+            Err(_) => Err(ApiError::Timeout(self.timeout).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for RequestLimitMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let response = next.run(request).await;
+
+        let handled = self.handled.fetch_add(1, Ordering::SeqCst) + 1;
+        if handled >= self.limit {
+            log::info!("Request limit {} reached", self.limit);
+            self.shutdown.notify_one();
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for ShutdownOnSuccessMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let response = next.run(request).await;
+        if response.status().is_success() {
+            log::info!("File downloaded");
+            self.shutdown.notify_one();
+        }
+
+        Ok(response)
+    }
+}
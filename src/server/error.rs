@@ -0,0 +1,157 @@
+use std::{fmt, time::Duration};
+
+use async_openai::error::OpenAIError;
+use async_trait::async_trait;
+use serde_json::json;
+use tide::{Middleware, Next, Request, StatusCode};
+
+/// Errors returned to API clients as JSON bodies: `{"error": {"code": ..., "message": ...}}`.
+/// Handlers may return it with `?`, the status code is set by the error response middleware.
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// Malformed JSON or invalid request fields
+    BadRequest(String),
+    PayloadTooLarge {
+        limit: usize,
+    },
+    /// LLM or other external service failed
+    Upstream(String),
+    Timeout(Duration),
+    /// Details are logged, but not exposed to the client
+    Internal,
+    /// Errors produced by the router, e.g. unknown route
+    Status(StatusCode),
+}
+
+/// Converts handler errors and empty error responses to JSON error bodies with matching status codes.
+pub(super) struct ErrorResponseMiddleware;
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BadRequest,
+            Self::PayloadTooLarge { .. } => StatusCode::PayloadTooLarge,
+            Self::Upstream(_) => StatusCode::BadGateway,
+            Self::Timeout(_) => StatusCode::GatewayTimeout,
+            Self::Internal => StatusCode::InternalServerError,
+            Self::Status(status) => *status,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::PayloadTooLarge { .. } => "payload_too_large",
+            Self::Upstream(_) => "upstream_error",
+            Self::Timeout(_) => "timeout",
+            Self::Internal => "internal_error",
+            Self::Status(StatusCode::NotFound) => "not_found",
+            Self::Status(StatusCode::MethodNotAllowed) => "method_not_allowed",
+            Self::Status(status) if status.is_client_error() => "client_error",
+            Self::Status(_) => "server_error",
+        }
+    }
+
+    /// Classifies error returned by a request handler using the whole chain of its causes.
+    ///
+    /// * `error`: handler error
+    fn classify(error: &tide::Error) -> Self {
+        let inner: &anyhow::Error = error.as_ref();
+        for cause in inner.chain() {
+            if let Some(api_error) = cause.downcast_ref::<ApiError>() {
+                return api_error.clone();
+            }
+            if let Some(json_error) = cause.downcast_ref::<serde_json::Error>() {
+                if error.status() == StatusCode::UnprocessableEntity {
+                    return Self::BadRequest(format!("Invalid JSON body: {json_error}"));
+                }
+            }
+            if let Some(openai_error) = cause.downcast_ref::<OpenAIError>() {
+                return Self::Upstream(format!("LLM request failed: {openai_error}"));
+            }
+            if let Some(reqwest_error) = cause.downcast_ref::<reqwest::Error>() {
+                return Self::Upstream(format!("External request failed: {reqwest_error}"));
+            }
+            if let Some(middleware_error) = cause.downcast_ref::<reqwest_middleware::Error>() {
+                return Self::Upstream(format!("External request failed: {middleware_error}"));
+            }
+        }
+
+        match error.status() {
+            StatusCode::InternalServerError => Self::Internal,
+            status => Self::Status(status),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadRequest(message) | Self::Upstream(message) => write!(f, "{message}"),
+            Self::PayloadTooLarge { limit } => {
+                write!(f, "Request body larger than {limit} bytes")
+            }
+            Self::Timeout(timeout) => {
+                write!(f, "Request not handled in {} s", timeout.as_secs_f32())
+            }
+            Self::Internal => write!(f, "Internal server error"),
+            Self::Status(status) => write!(f, "{}", status.canonical_reason()),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for ErrorResponseMiddleware {
+    async fn handle(&self, request: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let mut response = next.run(request).await;
+
+        let api_error = match response.error() {
+            Some(error) => ApiError::classify(error),
+            None if response.status().is_success() => return Ok(response),
+            None if response.is_empty() == Some(true) => ApiError::Status(response.status()),
+            None => return Ok(response),
+        };
+
+        response.set_status(api_error.status());
+        response.set_body(json!({
+            "error": {
+                "code": api_error.code(),
+                "message": api_error.to_string(),
+            }
+        }));
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_handler_errors() {
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let error = tide::Error::new(StatusCode::UnprocessableEntity, json_error);
+        assert_eq!(ApiError::classify(&error).status(), StatusCode::BadRequest);
+
+        let error = tide::Error::from(ApiError::Timeout(Duration::from_secs(1)));
+        assert_eq!(ApiError::classify(&error).code(), "timeout");
+
+        let error = tide::Error::from(
+            anyhow::Error::new(ApiError::bad_request("Question is empty"))
+                .context("Handler failed"),
+        );
+        assert_eq!(ApiError::classify(&error).status(), StatusCode::BadRequest);
+
+        let error = tide::Error::from(anyhow::anyhow!("Memory file is broken"));
+        let api_error = ApiError::classify(&error);
+        assert_eq!(api_error.status(), StatusCode::InternalServerError);
+        assert_eq!(api_error.to_string(), "Internal server error");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config::Config;
+
+/// Request header with conversation session id.
+pub const SESSION_HEADER: &str = "X-Session-Id";
+/// Session used by callers which do not send session id, e.g. the task checker.
+pub const DEFAULT_SESSION: &str = "default";
+/// Number of recent exchanges kept in session history.
+const HISTORY_LIMIT: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub question: String,
+    pub reply: String,
+}
+
+/// State of a single conversation. Requests of different sessions are handled independently.
+#[derive(Debug)]
+pub struct Session {
+    pub id: String,
+    history: Mutex<VecDeque<Exchange>>,
+}
+
+struct SessionEntry {
+    session: Arc<Session>,
+    last_used: Instant,
+}
+
+/// Sessions of a single API. Sessions not used longer than TTL are dropped.
+#[derive(Clone)]
+pub struct SessionStore {
+    sessions: Arc<Mutex<HashMap<String, SessionEntry>>>,
+    ttl: Duration,
+}
+
+impl Session {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Adds question and reply to the history, the oldest exchanges are dropped above the limit.
+    pub fn record(&self, question: &str, reply: &str) {
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.push_back(Exchange {
+            question: question.to_string(),
+            reply: reply.to_string(),
+        });
+        while history.len() > HISTORY_LIMIT {
+            history.pop_front();
+        }
+    }
+
+    pub fn history(&self) -> Vec<Exchange> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.iter().cloned().collect()
+    }
+
+    /// History formatted for LLM context, `None` when the conversation just started.
+    pub fn history_context(&self) -> Option<String> {
+        let history = self.history();
+        if history.is_empty() {
+            return None;
+        }
+
+        let exchanges = history
+            .iter()
+            .map(|e| format!("Q: {}\nA: {}", e.question, e.reply))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(format!("Previous conversation:\n{exchanges}"))
+    }
+}
+
+impl SessionStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(Duration::from_secs(config.session_ttl_secs))
+    }
+
+    /// Returns session with the id, creating it when it does not exist or has expired.
+    ///
+    /// * `id`: session id
+    pub fn get(&self, id: &str) -> Arc<Session> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+
+        let ttl = self.ttl;
+        sessions.retain(|id, entry| {
+            let alive = now.duration_since(entry.last_used) < ttl;
+            if !alive {
+                log::debug!("Session '{id}' expired");
+            }
+            alive
+        });
+
+        let entry = sessions.entry(id.to_string()).or_insert_with(|| {
+            log::debug!("Session '{id}' created");
+            SessionEntry {
+                session: Arc::new(Session::new(id)),
+                last_used: now,
+            }
+        });
+        entry.last_used = now;
+
+        entry.session.clone()
+    }
+}
+
+/// Reads session id from the request header or, when not present, from the request body field.
+/// Returns `None` when the client did not send any.
+///
+/// * `request`: API request
+/// * `body_session_id`: `session_id` field of the request body
+pub fn requested_session_id<State>(
+    request: &tide::Request<State>,
+    body_session_id: Option<&str>,
+) -> Option<String> {
+    request
+        .header(SESSION_HEADER)
+        .map(|h| h.last().as_str())
+        .or(body_session_id)
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+}
+
+/// Reads session id sent by the client, falling back to the default session.
+///
+/// * `request`: API request
+/// * `body_session_id`: `session_id` field of the request body
+pub fn session_id<State>(request: &tide::Request<State>, body_session_id: Option<&str>) -> String {
+    requested_session_id(request, body_session_id).unwrap_or_else(|| DEFAULT_SESSION.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_store_expiry() {
+        let store = SessionStore::new(Duration::from_millis(50));
+
+        store.get("a").record("Who am I?", "I do not know");
+        assert_eq!(store.get("a").history().len(), 1);
+        assert!(store.get("b").history().is_empty());
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(store.get("a").history().is_empty());
+    }
+
+    #[test]
+    fn test_session_history_limit() {
+        let session = Session::new("a");
+        for i in 0..HISTORY_LIMIT + 2 {
+            session.record(&format!("q{i}"), &format!("r{i}"));
+        }
+
+        let history = session.history();
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history[0].question, "q2");
+    }
+}
@@ -2,7 +2,7 @@ mod blogger;
 mod embedding;
 mod functions;
 mod gnome;
-mod google;
+pub(crate) mod google;
 mod helloapi;
 mod inprompt;
 mod knowledge;
@@ -10,8 +10,8 @@ mod liar;
 mod meme;
 mod moderation;
 mod optimaldb;
-mod ownapi;
-mod ownapipro;
+pub(crate) mod ownapi;
+pub(crate) mod ownapipro;
 mod people;
 mod rodo;
 mod scraper;
@@ -87,7 +87,7 @@ pub enum Task {
 
     /// run 'tools' task
     #[strum(serialize = "tools")]
-    Tools,
+    Tools(tools::ToolsArgs),
 
     /// run 'gnome' task
     #[strum(serialize = "gnome")]
@@ -116,6 +116,12 @@ pub enum Task {
 
 impl Task {
     pub async fn run(self, config: Config) -> anyhow::Result<()> {
+        if let Self::Tools(args) = &self {
+            if args.repl {
+                return tools::repl(&config).await;
+            }
+        }
+
         let task_name = self.to_string();
         log::info!("Start '{task_name}' task");
 
@@ -132,12 +138,15 @@ impl Task {
             Self::Whisper => whisper::run(&config, &token).await,
             Self::Functions => functions::run(&config, &token).await,
             Self::Rodo => rodo::run(&config, &token).await,
-            Self::Scraper => scraper::run(&config, &token).await,
+            Self::Scraper => {
+                scraper::run(&config, &token).await?;
+                return Ok(());
+            }
             Self::Whoami => whoami::run(&config, &token).await,
             Self::Search => search::run(&config, &token).await,
             Self::People => people::run(&config, &token).await,
             Self::Knowledge => knowledge::run(&config, &token).await,
-            Self::Tools => tools::run(&config, &token).await,
+            Self::Tools(_) => tools::run(&config, &token).await,
             Self::Gnome => gnome::run(&config, &token).await,
             Self::Ownapi => {
                 ownapi::run(&config, &token).await?;
@@ -147,7 +156,10 @@ impl Task {
                 ownapipro::run(&config, &token).await?;
                 return Ok(());
             }
-            Self::Meme => meme::run(&config, &token).await,
+            Self::Meme => {
+                meme::run(&config, &token).await?;
+                return Ok(());
+            }
             Self::Optimaldb => optimaldb::run(&config, &token).await,
             Self::Google => {
                 google::run(&config, &token).await?;
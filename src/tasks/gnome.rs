@@ -1,20 +1,13 @@
-use anyhow::{anyhow, bail};
-use async_openai::{
-    config::OpenAIConfig,
-    types::{
-        ChatCompletionRequestMessageContentPartImageArgs,
-        ChatCompletionRequestMessageContentPartTextArgs, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs, ImageUrlArgs, ImageUrlDetail,
-    },
-    Client,
-};
+use anyhow::bail;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use url::Url;
 
-use crate::{aidevs, config::Config};
-
-const MODEL: &str = "gpt-4-vision-preview";
+use crate::{
+    aidevs,
+    config::Config,
+    vision::{ImageDetail, ImageSource, Vision, VisionAnswer, ERROR_ANSWER},
+};
 
 #[derive(Debug, Deserialize)]
 struct GnomeTaskResponse {
@@ -38,45 +31,19 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         bail!("Code in response is not equal 0")
     }
 
-    let openai_config = OpenAIConfig::default();
-    let openai_client = Client::with_config(openai_config);
-
-    let request_message_text = ChatCompletionRequestMessageContentPartTextArgs::default()
-        .text(
-            [
-                &task_response.msg,
-                "hint: it won't always be a drawing of a gnome, return ERROR in this case",
-                "Answer concisely as possible",
-            ]
-            .join("\n"),
-        )
-        .build()?;
-    let image_url = ImageUrlArgs::default()
-        .url(task_response.url)
-        .detail(ImageUrlDetail::High)
-        .build()?;
-    let request_message_image = ChatCompletionRequestMessageContentPartImageArgs::default()
-        .image_url(image_url)
-        .build()?;
-    let request_message = ChatCompletionRequestUserMessageArgs::default()
-        .content(vec![
-            request_message_text.into(),
-            request_message_image.into(),
-        ])
-        .build()?;
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(MODEL)
-        .messages([request_message.into()])
-        .build()?;
-
-    let response = openai_client.chat().create(request).await?;
-    let answer = response
-        .choices
-        .into_iter()
-        .find_map(|c| c.message.content)
-        .ok_or(anyhow!("{MODEL} response do not contain answer."))?;
-
-    log::info!("{MODEL} answer: {answer}");
+    let vision = Vision::new(ImageDetail::High);
+    let question = [
+        task_response.msg.as_str(),
+        "hint: it won't always be a drawing of a gnome",
+    ]
+    .join("\n");
+    let answer = match vision
+        .ask(&[ImageSource::Url(task_response.url)], &question)
+        .await?
+    {
+        VisionAnswer::Answer(answer) => answer,
+        VisionAnswer::Rejected(_) => ERROR_ANSWER.to_string(),
+    };
 
     let payload = json!({ "answer" : answer});
     Ok(payload)
@@ -1,16 +1,24 @@
-use std::{sync::Arc, time::Duration};
+use std::{fmt::Write, sync::Arc};
 
 use anyhow::{anyhow, bail};
 use async_openai::{config::OpenAIConfig, Client};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use tide::StatusCode;
-use tokio::time::sleep;
 use url::Url;
 
-use crate::{aidevs, brave_search::BraveSearchClient, config::Config, utils};
+use crate::{
+    aidevs,
+    config::Config,
+    fetcher::Fetcher,
+    search_provider::{self, SearchHit, SearchProvider},
+    server::{ApiError, ApiServer, ServerOptions},
+    utils,
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
+const SEARCH_RESULTS_LIMIT: usize = 5;
+const PAGE_EXCERPT_LENGTH: usize = 1500;
+const MIN_CONFIDENCE: f32 = 0.6;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -31,11 +39,22 @@ struct GoogleRequest {
 #[derive(Debug, Deserialize, Serialize)]
 struct GoogleResponse {
     reply: Url,
+    confidence: f32,
 }
 
-struct GoogleApiState {
+#[derive(Debug, Deserialize)]
+struct ResultSelection {
+    index: Option<usize>,
+    #[serde(default)]
+    confidence: f32,
+    #[serde(default)]
+    reasoning: String,
+}
+
+pub(crate) struct GoogleApiState {
     openai_client: Client<OpenAIConfig>,
-    search_client: BraveSearchClient,
+    search_provider: Box<dyn SearchProvider>,
+    fetcher: Option<Fetcher>,
 }
 
 /// The task was to create an API that searches the internet and returns the URL associated with the given query.
@@ -43,23 +62,6 @@ struct GoogleApiState {
 /// * `config`: App configuration
 /// * `token`: Task token
 pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
-    let brave_search_api_key = config
-        .brave_search_api_key
-        .as_ref()
-        .ok_or(anyhow!("Brave Search API key not found in configuration"))?;
-    let api_listen_addr = config
-        .api_listen_address
-        .as_ref()
-        .ok_or(anyhow!("API listen address not specified"))?
-        .clone();
-    let mut api_tunnel_url = config
-        .api_tunnel_url
-        .as_ref()
-        .ok_or(anyhow!("Ngrok tunnel URL not found in configuration"))?
-        .clone();
-    api_tunnel_url.set_path("search");
-    log::info!("API tunneled endpoint: {api_tunnel_url}");
-
     let task_response = aidevs::get_task::<GoogleTaskResponse>(config, token).await?;
     log::debug!("Task API response: {task_response:#?}");
     log::info!("Task message: {}", task_response.msg);
@@ -68,59 +70,159 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
         bail!("Code in response is not equal 0")
     }
 
-    let mut search_client = BraveSearchClient::new(brave_search_api_key)?;
-    search_client.set_country("PL")?;
+    let mut server = ApiServer::new((), ServerOptions::from(config));
+    server.mount("/search", api(config, "/search")?);
+
+    server.run_task(config, token, "search").await
+}
+
+/// Creates API which finds URL answering questions sent to the route.
+///
+/// * `config`: App configuration
+/// * `route`: endpoint path
+pub(crate) fn api(
+    config: &Config,
+    route: &str,
+) -> anyhow::Result<tide::Server<Arc<GoogleApiState>>> {
+    let search_provider = search_provider::from_config(config)?;
 
     let openai_config = OpenAIConfig::default();
     let openai_client = Client::with_config(openai_config);
 
+    let fetcher = match config.search_fetch_pages {
+        true => Some(Fetcher::from_config(config)?),
+        false => None,
+    };
+
     let api_state = GoogleApiState {
         openai_client,
-        search_client,
+        search_provider,
+        fetcher,
     };
-    let api_state = Arc::new(api_state);
-    let mut app = tide::with_state(api_state);
-    app.at("/search").post(search_request_handler);
-
-    let api_future = tokio::spawn(app.listen(api_listen_addr));
-    sleep(Duration::from_secs(1)).await;
-
-    let payload = json!({ "answer" : api_tunnel_url});
-    let answer_response = aidevs::post_answer(config, token, &payload).await?;
-    if answer_response.code != 0 {
-        bail!(
-            "Post answer failed: [{}] {}",
-            answer_response.code,
-            answer_response.msg
-        )
-    }
-
-    api_future.await??;
-
-    Ok(())
+    let mut app = tide::with_state(Arc::new(api_state));
+    app.at(route).post(search_request_handler);
+    Ok(app)
 }
 
 async fn search_request_handler(mut request: tide::Request<Arc<GoogleApiState>>) -> tide::Result {
     let GoogleRequest { question } = request.body_json().await?;
+    if question.trim().is_empty() {
+        return Err(ApiError::bad_request("Field 'question' must not be empty").into());
+    }
     log::debug!("Received question: {question}");
 
     let state = request.state();
+    let (hit, confidence) = find_best_result(state, &question).await?;
+    log::info!("Selected {} with confidence {confidence}", hit.url);
 
-    let context = "Rephrase provided query to format which can be used as input for search enginge like Google";
-    let response = utils::ask_llm(&state.openai_client, MODEL, &question, Some(context)).await?;
-
-    let result = state.search_client.search(&response).await?;
-    let reply = result
-        .web
-        .results
-        .first()
-        .ok_or(anyhow!("No search results"))?
-        .url
-        .clone();
-
-    let response_body = tide::Body::from_json(&GoogleResponse { reply })?;
+    let reply = GoogleResponse {
+        reply: hit.url,
+        confidence,
+    };
+    let response_body = tide::Body::from_json(&reply)?;
     let mut response = tide::Response::new(StatusCode::Ok);
     response.set_body(response_body);
 
     Ok(response)
 }
+
+/// Searches with rephrased question and lets LLM choose the result which answers it.
+/// When no result is good enough, the question is rephrased once more with different keywords.
+async fn find_best_result(
+    state: &GoogleApiState,
+    question: &str,
+) -> anyhow::Result<(SearchHit, f32)> {
+    let rewrite_contexts = [
+        "Rephrase provided query to format which can be used as input for search enginge like Google. Reply only with the query.".to_string(),
+        "Rephrase provided query to a search engine query using different keywords and synonyms than obvious ones. Reply only with the query.".to_string(),
+    ];
+
+    let mut best: Option<(SearchHit, f32)> = None;
+    for rewrite_context in rewrite_contexts {
+        let query = utils::ask_llm(
+            &state.openai_client,
+            MODEL,
+            question,
+            Some(&rewrite_context),
+        )
+        .await?;
+
+        let hits = state
+            .search_provider
+            .search(&query, SEARCH_RESULTS_LIMIT)
+            .await?;
+        log::debug!(
+            "'{}' search provider returned {} results for '{query}'",
+            state.search_provider.name(),
+            hits.len()
+        );
+        if hits.is_empty() {
+            continue;
+        }
+
+        let selection = select_result(state, question, &hits).await?;
+        log::info!(
+            "Result selection: index {:?}, confidence {}, reasoning: {}",
+            selection.index,
+            selection.confidence,
+            selection.reasoning
+        );
+
+        let Some(hit) = selection.index.and_then(|i| hits.get(i)) else {
+            log::info!("No matching result for '{query}'");
+            continue;
+        };
+
+        if best.as_ref().is_none_or(|(_, c)| selection.confidence > *c) {
+            best = Some((hit.clone(), selection.confidence));
+        }
+        if selection.confidence >= MIN_CONFIDENCE {
+            break;
+        }
+        log::info!("Confidence below {MIN_CONFIDENCE}, trying another query");
+    }
+
+    best.ok_or(anyhow!("No search result matches the question"))
+}
+
+async fn select_result(
+    state: &GoogleApiState,
+    question: &str,
+    hits: &[SearchHit],
+) -> anyhow::Result<ResultSelection> {
+    let mut results = String::new();
+    for (index, hit) in hits.iter().enumerate() {
+        let _ = writeln!(
+            results,
+            "[{index}] {}\nURL: {}\n{}",
+            hit.title, hit.url, hit.description
+        );
+        if let Some(excerpt) = page_excerpt(state, hit).await {
+            let _ = writeln!(results, "Page excerpt: {excerpt}");
+        }
+        results.push('\n');
+    }
+
+    let context = [
+        "You are given a question and a numbered list of search results.",
+        "Choose the result which best answers the question.",
+        "Reply only with JSON object: {\"index\": <result number or null if none matches>, \"confidence\": <number from 0 to 1>, \"reasoning\": \"<short explanation>\"}",
+        "###",
+        &results,
+    ]
+    .join("\n");
+
+    let answer = utils::ask_llm(&state.openai_client, MODEL, question, Some(&context)).await?;
+    utils::parse_json_answer(&answer)
+}
+
+async fn page_excerpt(state: &GoogleApiState, hit: &SearchHit) -> Option<String> {
+    let fetcher = state.fetcher.as_ref()?;
+    match fetcher.get_document(hit.url.clone()).await {
+        Ok(document) => Some(document.text.chars().take(PAGE_EXCERPT_LENGTH).collect()),
+        Err(err) => {
+            log::warn!("Can not fetch {} content: {err}", hit.url);
+            None
+        }
+    }
+}
@@ -1,9 +1,9 @@
-use anyhow::{anyhow, bail};
+use anyhow::bail;
 use async_openai::{config::OpenAIConfig, Client};
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::{aidevs, config::Config, utils::ask_llm};
+use crate::{aidevs, config::Config, entities::EntityExtractor, utils::ask_llm};
 
 const MODEL: &str = "gpt-3.5-turbo";
 
@@ -29,40 +29,43 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     }
     log::info!("Question: {}", task_response.question);
 
-    let name = find_capitalized_word(&task_response.question)
-        .ok_or(anyhow!("Name in question not found."))?;
+    let person = EntityExtractor::from_config(config)?
+        .extract_person(&task_response.question)
+        .await?;
+    let name = person.name.as_str();
+    log::info!("Person in question: {}", person.full_name());
 
+    let context = build_context(&task_response.input, name);
+    log::debug!("Context for LLM: {context}");
+
+    let openai_config = OpenAIConfig::default();
+    let client = Client::with_config(openai_config);
+    let answer = ask_llm(&client, MODEL, &task_response.question, Some(&context)).await?;
+
+    let payload = json!({ "answer" : answer});
+    Ok(payload)
+}
+
+/// Context for LLM with sentences about the person only.
+///
+/// * `input`: sentences about different people
+/// * `name`: first name of the person
+fn build_context(input: &[String], name: &str) -> String {
     let context_header = [
         "Answer on my question only using data prowided after ### markers.",
         "Answer concisely as possible",
         "###",
     ];
-    let context = context_header
+    context_header
         .into_iter()
         .chain(
-            task_response
-                .input
+            input
                 .iter()
                 .filter(|sentence| sentence.contains(name))
                 .map(|s| s.as_str()),
         )
         .collect::<Vec<_>>()
-        .join("\n");
-
-    log::debug!("Context for LLM: {context}");
-
-    let openai_config = OpenAIConfig::default();
-    let client = Client::with_config(openai_config);
-    let answer = ask_llm(&client, MODEL, &task_response.question, Some(&context)).await?;
-
-    let payload = json!({ "answer" : answer});
-    Ok(payload)
-}
-
-fn find_capitalized_word(line: &str) -> Option<&str> {
-    line.split_whitespace()
-        .find(|word| word.chars().next().map_or(false, |c| c.is_uppercase()))
-        .map(|s| s.trim_end_matches(|c: char| !c.is_alphabetic()))
+        .join("\n")
 }
 
 #[cfg(test)]
@@ -70,9 +73,16 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_find_capitalized_word() {
-        assert_eq!(find_capitalized_word("siema Michał!"), Some("Michał"));
-        assert_eq!(find_capitalized_word("no body is here!"), None);
-        assert_eq!(find_capitalized_word("my name is James."), Some("James"));
+    fn test_build_context() {
+        let input = [
+            "Michał lubi pizzę.",
+            "Krystyna mieszka w Krakowie.",
+            "Michał ma psa.",
+        ]
+        .map(String::from);
+
+        let context = build_context(&input, "Michał");
+        assert!(context.ends_with("###\nMichał lubi pizzę.\nMichał ma psa."));
+        assert!(build_context(&input, "James").ends_with("###"));
     }
 }
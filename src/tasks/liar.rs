@@ -1,19 +1,18 @@
 use anyhow::{anyhow, bail};
-use async_openai::{
-    config::OpenAIConfig,
-    types::{
-        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs,
-    },
-    Client,
-};
+use async_openai::{config::OpenAIConfig, Client};
 use reqwest::multipart::Form;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    fetcher::Fetcher,
+    guardrails::{GuardInput, Guardrail, RelevanceCheck},
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
+/// Answers scored lower are considered untruthful.
+const MIN_SCORE: f32 = 0.5;
 
 #[derive(Debug, Deserialize)]
 struct LiarTaskResponse {
@@ -32,38 +31,19 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     let answer = get_task_api_answer(config, token, question).await?;
     log::info!("Task API answer: {answer}");
 
-    let openai_config = OpenAIConfig::default();
-    let client = Client::with_config(openai_config);
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(MODEL)
-        .messages([
-            ChatCompletionRequestSystemMessageArgs::default()
-                .content("You are a verifier of the truthfulness of answers. Respond briefly with YES or NO whether the given question and answer match.")
-                .build()?
-                .into(),
-            ChatCompletionRequestUserMessageArgs::default()
-                .content(format!("{question}\n\n{answer}"))
-                .build()?
-                .into()
-        ]).build()?;
-
-    let response = client.chat().create(request).await?;
-    let mut verdicts = response
-        .choices
-        .into_iter()
-        .filter_map(|c| c.message.content)
-        .collect::<Vec<_>>();
-
-    let firs_verdict = verdicts
-        .pop()
-        .ok_or(anyhow!("Model response do not contain any verdicts"))?;
-
-    log::debug!("Versict: {firs_verdict}");
-    if firs_verdict != "YES" && firs_verdict != "NO" {
-        bail!("Model verdict is not equal YES or NO");
-    }
+    let check = RelevanceCheck::new(Client::with_config(OpenAIConfig::default()), MODEL);
+    let input = GuardInput::new(&answer).with_question(question);
+    let verdict = check
+        .check(&input)
+        .await?
+        .ok_or(anyhow!("Relevance check did not verify the answer"))?;
+    log::debug!("Verdict: {verdict:?}");
 
-    let payload = json!({ "answer" : firs_verdict});
+    let answer = match verdict.score >= MIN_SCORE {
+        true => "YES",
+        false => "NO",
+    };
+    let payload = json!({ "answer" : answer});
     Ok(payload)
 }
 
@@ -76,13 +56,8 @@ async fn get_task_api_answer(
     url.set_path(&format!("task/{token}"));
 
     let form = Form::new().text("question", question);
-    let api_client = reqwest::Client::new();
-    let task_response = api_client
-        .post(url)
-        .multipart(form)
-        .send()
-        .await?
-        .json::<LiarTaskResponse>()
+    let task_response = Fetcher::from_config(config)?
+        .post_multipart::<LiarTaskResponse>(url, form)
         .await?;
     log::debug!("Task API response: {task_response:#?}");
     log::info!("Task message: {}", task_response.msg);
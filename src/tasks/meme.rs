@@ -1,18 +1,23 @@
 use anyhow::{anyhow, bail};
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::json;
 use url::Url;
 
 use crate::{
     aidevs,
     config::Config,
+    fetcher::Fetcher,
+    meme_renderer::{self, MemeBackend, MemeRenderer},
     render_form::{
         RenderFormClient, RenderFormRenderDataBuilder, RenderFormRenderDataField,
         RenderFormRenderRequest,
     },
+    server::{ApiServer, ServerOptions},
 };
 
 const RENDER_FORM_TEMPLATE: &str = "lively-snakes-smash-gently-1459";
+/// Route under which locally rendered memes are hosted.
+const MEMES_ROUTE: &str = "/memes";
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -26,15 +31,12 @@ struct MemeTaskResponse {
 }
 
 /// The task was to generate a meme from the received image and text.
+/// The meme is rendered by RenderForm or locally, in the latter case it is hosted
+/// by the API server until the task checker downloads it or API_SHUTDOWN_AFTER requests are handled.
 ///
 /// * `config`: App configuration
 /// * `token`: Task token
-pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
-    let render_form_api_key = config
-        .render_form_api_key
-        .as_ref()
-        .ok_or(anyhow!("RenderForm API key not found in configuration"))?;
-
+pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
     let task_response = aidevs::get_task::<MemeTaskResponse>(config, token).await?;
     log::info!("Task message: {}", task_response.msg);
 
@@ -42,6 +44,23 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         bail!("Code in response is not equal 0")
     }
 
+    log::info!("Using '{}' meme backend", config.meme_backend);
+    match config.meme_backend {
+        MemeBackend::RenderForm => render_remote(config, token, task_response).await,
+        MemeBackend::Local => render_local(config, token, task_response).await,
+    }
+}
+
+async fn render_remote(
+    config: &Config,
+    token: &str,
+    task_response: MemeTaskResponse,
+) -> anyhow::Result<()> {
+    let render_form_api_key = config
+        .render_form_api_key
+        .as_ref()
+        .ok_or(anyhow!("RenderForm API key not found in configuration"))?;
+
     let render_client = RenderFormClient::new(render_form_api_key);
     let request_data = RenderFormRenderDataBuilder::new()
         .set(
@@ -60,5 +79,35 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     log::info!("Rendered image URL: {}", response.href);
 
     let payload = json!({ "answer" : response.href});
-    Ok(payload)
+    let answer_response = aidevs::post_answer(config, token, &payload).await?;
+    if answer_response.code != 0 {
+        bail!(answer_response.msg)
+    }
+
+    Ok(())
+}
+
+async fn render_local(
+    config: &Config,
+    token: &str,
+    task_response: MemeTaskResponse,
+) -> anyhow::Result<()> {
+    let renderer = MemeRenderer::from_config(config)?;
+
+    let fetcher = Fetcher::from_config(config)?;
+    let image = fetcher.get(task_response.image).await?;
+
+    let text = task_response.text;
+    let png = tokio::task::spawn_blocking(move || renderer.render(&image.body, &text)).await??;
+    let file_name = meme_renderer::save(&config.meme_output_dir, &png)?;
+    log::info!(
+        "Rendered image saved to {}",
+        config.meme_output_dir.join(&file_name).display()
+    );
+
+    let mut server = ApiServer::new((), ServerOptions::from(config));
+    let route = format!("{MEMES_ROUTE}/{file_name}");
+    server.serve_file_once(&route, &config.meme_output_dir.join(&file_name))?;
+
+    server.run_task(config, token, &route).await
 }
@@ -1,13 +1,8 @@
 use anyhow::bail;
-use async_openai::{
-    config::OpenAIConfig,
-    types::{CreateModerationRequestArgs, TextModerationModel},
-    Client,
-};
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::{aidevs, config::Config};
+use crate::{aidevs, config::Config, moderation::Moderator};
 
 #[derive(Debug, Deserialize)]
 struct ModerationTaskResponse {
@@ -29,22 +24,12 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         bail!("Code in response is not equal 0")
     }
 
-    let openai_config = OpenAIConfig::default();
-    let client = Client::with_config(openai_config);
-    let request = CreateModerationRequestArgs::default()
-        .input(task_response.input)
-        .model(TextModerationModel::Latest)
-        .build()?;
+    let moderator = Moderator::from_config(config)?;
+    let results = moderator.moderate(&task_response.input).await?;
+    log::debug!("Moderation results: {}", serde_json::to_string(&results)?);
 
-    let model_response = client.moderations().create(request).await?;
-
-    let flags = model_response
-        .results
-        .iter()
-        .map(|r| r.flagged as u8)
-        .collect::<Vec<_>>();
-
-    log::debug!("Model response flags: {flags:?}");
+    let flags = results.iter().map(|r| r.flagged as u8).collect::<Vec<_>>();
+    log::debug!("Moderation flags: {flags:?}");
 
     let payload = json!({ "answer" : flags});
     Ok(payload)
@@ -9,7 +9,7 @@ use url::Url;
 
 const DATABASE_SIZE_LIMIT: usize = 9 * 1024 - 256;
 
-use crate::{aidevs, config::Config, utils};
+use crate::{aidevs, config::Config, fetcher::Fetcher, utils};
 
 #[derive(Debug, Deserialize)]
 struct OptimaldbTaskResponse {
@@ -29,9 +29,9 @@ impl FriendsDatabase {
     // const OPTIMALIZATION_MODEL: &'static str = "gpt-4";
     const OPTIMALIZATION_MODEL: &'static str = "gpt-3.5-turbo";
 
-    async fn download(url: Url) -> anyhow::Result<FriendsDatabase> {
+    async fn download(fetcher: &Fetcher, url: Url) -> anyhow::Result<FriendsDatabase> {
         log::info!("Fetching friends database from {url}");
-        let response = reqwest::get(url).await?.json::<FriendsDatabase>().await?;
+        let response = fetcher.get_json::<FriendsDatabase>(url).await?;
 
         log::debug!(
             "Downloaded friends database size: {} kB",
@@ -45,7 +45,7 @@ impl FriendsDatabase {
         self.friends
             .values()
             .flat_map(|r| r.iter())
-            .fold(0, |s, r| s + r.as_bytes().len())
+            .fold(0, |s, r| s + r.len())
     }
 
     async fn optimize(&mut self, openai_client: &Client<OpenAIConfig>) -> anyhow::Result<()> {
@@ -124,7 +124,8 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     }
     log::info!("Task hint: {}", task_response.hint);
 
-    let mut database = FriendsDatabase::download(task_response.database).await?;
+    let fetcher = Fetcher::from_config(config)?;
+    let mut database = FriendsDatabase::download(&fetcher, task_response.database).await?;
 
     let openai_config = OpenAIConfig::default();
     let openai_client = Client::with_config(openai_config);
@@ -1,14 +1,18 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
-use anyhow::{anyhow, bail};
+use anyhow::bail;
 use async_openai::{config::OpenAIConfig, Client};
-use chrono::Local;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use tide::StatusCode;
-use tokio::time::sleep;
 
-use crate::{aidevs, config::Config, utils};
+use crate::{
+    aidevs,
+    config::Config,
+    moderation::Moderator,
+    server::{ApiError, ApiServer, ServerOptions},
+    session::{self, SessionStore},
+    temporal, utils,
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
 
@@ -25,6 +29,7 @@ struct OwnapiTaskResponse {
 #[derive(Debug, Deserialize, Serialize)]
 struct OwnapiRequest {
     question: String,
+    session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -33,18 +38,22 @@ struct OwnapiResponse {
 }
 
 #[derive(Clone)]
-struct OwnapiState {
+pub(crate) struct OwnapiState {
     openai_client: Arc<Client<OpenAIConfig>>,
-    llm_context: Arc<Option<String>>,
+    llm_context: Arc<String>,
+    sessions: SessionStore,
+    moderator: Option<Arc<Moderator>>,
 }
 
 impl OwnapiState {
-    fn new(llm_context: Option<String>) -> Self {
+    fn new(llm_context: String, sessions: SessionStore, moderator: Option<Moderator>) -> Self {
         let openai_config = OpenAIConfig::default();
         let openai_client = Client::with_config(openai_config);
         Self {
             openai_client: Arc::new(openai_client),
             llm_context: Arc::new(llm_context),
+            sessions,
+            moderator: moderator.map(Arc::new),
         }
     }
 }
@@ -54,19 +63,6 @@ impl OwnapiState {
 /// * `config`: App configuration
 /// * `token`: Task token
 pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
-    let api_listen_addr = config
-        .api_listen_address
-        .as_ref()
-        .ok_or(anyhow!("API listen address not specified"))?
-        .clone();
-    let mut api_tunnel_url = config
-        .api_tunnel_url
-        .as_ref()
-        .ok_or(anyhow!("Ngrok tunnel URL not found in configuration"))?
-        .clone();
-    api_tunnel_url.set_path("ownapi");
-    log::info!("API tunneled endpoint: {api_tunnel_url}");
-
     let task_response = aidevs::get_task::<OwnapiTaskResponse>(config, token).await?;
     log::debug!("Task API response: {task_response:#?}");
     log::info!("Task message: {}", task_response.msg);
@@ -75,47 +71,67 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
         bail!("Code in response is not equal 0")
     }
 
-    let today = Local::now();
+    let mut server = ApiServer::new((), ServerOptions::from(config));
+    server.mount("/ownapi", api(config, "/ownapi")?);
+
+    server.run_task(config, token, "ownapi").await
+}
+
+/// Creates API answering questions sent to the route.
+///
+/// * `config`: App configuration
+/// * `route`: endpoint path
+pub(crate) fn api(config: &Config, route: &str) -> anyhow::Result<tide::Server<OwnapiState>> {
     let llm_context = [
         "Answer concisely as possible",
         "If you do not know answer for the question say 'I do not know'",
-        &format!("Today is: {today}"),
     ]
     .join("\n");
 
-    let state = OwnapiState::new(Some(llm_context));
+    let state = OwnapiState::new(
+        llm_context,
+        SessionStore::from_config(config),
+        Moderator::screening_from_config(config)?,
+    );
     let mut app = tide::with_state(state);
-    app.at("/ownapi").post(ownapi_request_handler);
-
-    let api_future = tokio::spawn(app.listen(api_listen_addr));
-    sleep(Duration::from_secs(1)).await;
-
-    let payload = json!({ "answer" : api_tunnel_url});
-    let answer_response = aidevs::post_answer(config, token, &payload).await?;
-    if answer_response.code != 0 {
-        bail!(
-            "Post answer failed: [{}] {}",
-            answer_response.code,
-            answer_response.msg
-        )
-    }
-
-    api_future.await??;
-
-    Ok(())
+    app.at(route).post(ownapi_request_handler);
+    Ok(app)
 }
 
 async fn ownapi_request_handler(mut request: tide::Request<OwnapiState>) -> tide::Result {
-    let OwnapiRequest { question } = request.body_json().await?;
-    log::debug!("Received question: {question}");
+    let OwnapiRequest {
+        question,
+        session_id,
+    } = request.body_json().await?;
+    if question.trim().is_empty() {
+        return Err(ApiError::bad_request("Field 'question' must not be empty").into());
+    }
+    // History is kept only for clients sending session id, other requests are stateless
+    let session_id = session::requested_session_id(&request, session_id.as_deref());
+    log::debug!("Received question in session {session_id:?}: {question}");
 
     let state = request.state();
-    let OwnapiState {
-        openai_client,
-        llm_context,
-    } = state;
-
-    let reply = utils::ask_llm(openai_client, MODEL, &question, llm_context.as_deref()).await?;
+    if let Some(moderator) = &state.moderator {
+        if let Some(result) = moderator.screen(&question).await? {
+            let message = format!(
+                "Question rejected by moderation: {}",
+                result.flagged_categories()
+            );
+            return Err(ApiError::bad_request(message).into());
+        }
+    }
+    let session = session_id.map(|id| state.sessions.get(&id));
+    // Dates are resolved per request, the server may run for days
+    let date_context = temporal::date_context(&question, temporal::today());
+    let llm_context = match session.as_ref().and_then(|s| s.history_context()) {
+        Some(history) => format!("{}\n{date_context}\n{history}", state.llm_context),
+        None => format!("{}\n{date_context}", state.llm_context),
+    };
+
+    let reply = utils::ask_llm(&state.openai_client, MODEL, &question, Some(&llm_context)).await?;
+    if let Some(session) = &session {
+        session.record(&question, &reply);
+    }
     let response_body = tide::Body::from_json(&OwnapiResponse { reply })?;
     let mut response = tide::Response::new(StatusCode::Ok);
     response.set_body(response_body);
@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use anyhow::{anyhow, bail};
 use async_openai::{
@@ -10,15 +10,22 @@ use async_openai::{
     },
     Client,
 };
-use chrono::Local;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tide::StatusCode;
-use tokio::{sync::Mutex, time::sleep};
 
-use crate::{aidevs, config::Config, utils};
+use crate::{
+    aidevs,
+    config::Config,
+    memory::Memory,
+    moderation::Moderator,
+    server::{ApiError, ApiServer, ServerOptions},
+    session::{self, Session, SessionStore},
+    temporal, utils,
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
+const RECALLED_FACTS: usize = 5;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -33,6 +40,7 @@ struct OwnapiProTaskResponse {
 #[derive(Debug, Deserialize, Serialize)]
 struct OwnapiProRequest {
     question: String,
+    session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -51,58 +59,145 @@ struct OwnapiProAnswerFuncArgs {
     question: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct OwnapiProForgetFuncArgs {
+    description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OwnapiProUpdateFuncArgs {
+    description: String,
+    data: String,
+    category: Option<String>,
+}
+
 struct OwnapiProContext {
     openai_client: Client<OpenAIConfig>,
     llm_context: String,
     chat_tools: Vec<ChatCompletionTool>,
+    memory: Memory,
+    sessions: SessionStore,
+    moderator: Option<Moderator>,
 }
 
 impl OwnapiProContext {
-    fn new() -> anyhow::Result<Self> {
+    async fn new(config: &Config) -> anyhow::Result<Self> {
         let openai_config = OpenAIConfig::default();
         let openai_client = Client::with_config(openai_config);
 
-        let today = Local::now();
         let llm_context = [
             "Answer concisely as possible",
             "If you do not know answer for the question say 'I do not know'",
-            &format!("Today is: {today}"),
         ]
         .join("\n");
 
         let chat_tools = Self::chat_tools()?;
+        let memory = Memory::from_config(config).await?;
+        let sessions = SessionStore::from_config(config);
+        let moderator = Moderator::screening_from_config(config)?;
 
         Ok(Self {
             openai_client,
             llm_context,
             chat_tools,
+            memory,
+            sessions,
+            moderator,
         })
     }
 
     async fn tool_fn_answer(
         &self,
+        session: &Session,
         args: OwnapiProAnswerFuncArgs,
     ) -> anyhow::Result<OwnapiProResponse> {
+        let facts = self
+            .memory
+            .recall(&session.id, &args.question, RECALLED_FACTS)
+            .await?;
+        // Dates are resolved per request, the server may run for days
+        let mut llm_context = format!(
+            "{}\n{}",
+            self.llm_context,
+            temporal::date_context(&args.question, temporal::today())
+        );
+        for fact in facts {
+            llm_context.push_str(&format!(
+                "\n Fact about me: {} {}",
+                fact.category, fact.data
+            ));
+        }
+        if let Some(history) = session.history_context() {
+            llm_context.push_str(&format!("\n{history}"));
+        }
+
         let reply = utils::ask_llm(
             &self.openai_client,
             MODEL,
             &args.question,
-            Some(&self.llm_context),
+            Some(&llm_context),
         )
         .await?;
 
         Ok(OwnapiProResponse { reply })
     }
 
-    fn tool_fn_remember(
-        &mut self,
+    async fn tool_fn_remember(
+        &self,
+        session: &Session,
         args: OwnapiProRemeberFuncArgs,
     ) -> anyhow::Result<OwnapiProResponse> {
         log::debug!("Data to remember: {args:?}");
-        self.llm_context.push_str(&format!(
-            "\n Fact about me: {} {}",
-            args.category, args.data
-        ));
+        // Relative dates lose their meaning the next day, so exact dates are remembered with them
+        let mentions = temporal::find_dates(&args.data, temporal::today());
+        let mut data = args.data;
+        for mention in mentions {
+            data.push_str(&format!(" ('{}' is {})", mention.expression, mention.date));
+        }
+        self.memory
+            .remember(&session.id, &args.category, &data)
+            .await?;
+
+        Ok(OwnapiProResponse { reply: "Ok".into() })
+    }
+
+    async fn tool_fn_forget(
+        &self,
+        session: &Session,
+        args: OwnapiProForgetFuncArgs,
+    ) -> anyhow::Result<OwnapiProResponse> {
+        log::debug!("Data to forget: {args:?}");
+        let reply = match self.memory.forget(&session.id, &args.description).await? {
+            Some(_) => "Ok",
+            None => "I do not remember it",
+        };
+
+        Ok(OwnapiProResponse {
+            reply: reply.into(),
+        })
+    }
+
+    async fn tool_fn_update(
+        &self,
+        session: &Session,
+        args: OwnapiProUpdateFuncArgs,
+    ) -> anyhow::Result<OwnapiProResponse> {
+        log::debug!("Data to update: {args:?}");
+        let updated = self
+            .memory
+            .update(
+                &session.id,
+                &args.description,
+                &args.data,
+                args.category.as_deref(),
+            )
+            .await?;
+        if updated.is_none() {
+            let category = args.category.as_deref().unwrap_or("other");
+            self.memory
+                .remember(&session.id, category, &args.data)
+                .await?;
+        }
 
         Ok(OwnapiProResponse { reply: "Ok".into() })
     }
@@ -140,31 +235,75 @@ impl OwnapiProContext {
             }))
             .build()?;
 
-        let tools = vec![
-            ChatCompletionToolArgs::default()
-                .r#type(ChatCompletionToolType::Function)
-                .function(remember_function)
-                .build()?,
+        let forget_function = FunctionObjectArgs::default()
+            .name("forget")
+            .description("Forget previously remembered data")
+            .parameters(json!({
+                "type": "object",
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "Description of data to forget"
+                    },
+                }
+            }))
+            .build()?;
+
+        let update_function = FunctionObjectArgs::default()
+            .name("update")
+            .description("Change previously remembered data")
+            .parameters(json!({
+                "type": "object",
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "Description of data to change"
+                    },
+                    "data": {
+                        "type": "string",
+                        "description": "New data"
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Data category"
+                    },
+                }
+            }))
+            .build()?;
+
+        let tools = [
+            remember_function,
+            answer_function,
+            forget_function,
+            update_function,
+        ]
+        .into_iter()
+        .map(|function| {
             ChatCompletionToolArgs::default()
                 .r#type(ChatCompletionToolType::Function)
-                .function(answer_function)
-                .build()?,
-        ];
+                .function(function)
+                .build()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
         Ok(tools)
     }
 
     async fn handle(
-        &mut self,
+        &self,
+        session: &Session,
         tool_call: ChatCompletionMessageToolCall,
     ) -> anyhow::Result<OwnapiProResponse> {
         match tool_call.r#type {
-            ChatCompletionToolType::Function => self.handle_function_call(tool_call.function).await,
+            ChatCompletionToolType::Function => {
+                self.handle_function_call(session, tool_call.function).await
+            }
         }
     }
 
     async fn handle_function_call(
-        &mut self,
+        &self,
+        session: &Session,
         function_call: FunctionCall,
     ) -> anyhow::Result<OwnapiProResponse> {
         log::debug!("Calling '{}' function", function_call.name);
@@ -172,12 +311,20 @@ impl OwnapiProContext {
         match function_call.name.as_str() {
             "answer" => {
                 let args: OwnapiProAnswerFuncArgs = serde_json::from_str(&function_call.arguments)?;
-                self.tool_fn_answer(args).await
+                self.tool_fn_answer(session, args).await
             }
             "remember" => {
                 let args: OwnapiProRemeberFuncArgs =
                     serde_json::from_str(&function_call.arguments)?;
-                self.tool_fn_remember(args)
+                self.tool_fn_remember(session, args).await
+            }
+            "forget" => {
+                let args: OwnapiProForgetFuncArgs = serde_json::from_str(&function_call.arguments)?;
+                self.tool_fn_forget(session, args).await
+            }
+            "update" => {
+                let args: OwnapiProUpdateFuncArgs = serde_json::from_str(&function_call.arguments)?;
+                self.tool_fn_update(session, args).await
             }
 
             other => bail!("Unexptected function name: {other}"),
@@ -191,19 +338,6 @@ impl OwnapiProContext {
 /// * `config`: App configuration
 /// * `token`: Task token
 pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
-    let api_listen_addr = config
-        .api_listen_address
-        .as_ref()
-        .ok_or(anyhow!("API listen address not specified"))?
-        .clone();
-    let mut api_tunnel_url = config
-        .api_tunnel_url
-        .as_ref()
-        .ok_or(anyhow!("Ngrok tunnel URL not found in configuration"))?
-        .clone();
-    api_tunnel_url.set_path("ownapipro");
-    log::info!("API tunneled endpoint: {api_tunnel_url}");
-
     let task_response = aidevs::get_task::<OwnapiProTaskResponse>(config, token).await?;
     log::debug!("Task API response: {task_response:#?}");
     log::info!("Task message: {}", task_response.msg);
@@ -212,66 +346,104 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
         bail!("Code in response is not equal 0")
     }
 
-    let api_state = OwnapiProContext::new()?;
-    let api_state = Arc::new(Mutex::new(api_state));
-    let mut app = tide::with_state(api_state);
-    app.at("/ownapipro").post(ownapipro_request_handler);
-
-    let api_future = tokio::spawn(app.listen(api_listen_addr));
-    sleep(Duration::from_secs(1)).await;
-
-    let payload = json!({ "answer" : api_tunnel_url});
-    let answer_response = aidevs::post_answer(config, token, &payload).await?;
-    if answer_response.code != 0 {
-        bail!(
-            "Post answer failed: [{}] {}",
-            answer_response.code,
-            answer_response.msg
-        )
+    let mut server = ApiServer::new((), ServerOptions::from(config));
+    let assistant = OwnapiProAssistant::new(config).await?;
+    server.mount("/ownapipro", api(assistant, "/ownapipro"));
+
+    server.run_task(config, token, "ownapipro").await
+}
+
+/// Creates API which remembers data or answers questions sent to the route.
+///
+/// * `assistant`: assistant handling requests, may be shared with other APIs
+/// * `route`: endpoint path
+pub(crate) fn api(assistant: OwnapiProAssistant, route: &str) -> tide::Server<OwnapiProAssistant> {
+    let mut app = tide::with_state(assistant);
+    app.at(route).post(ownapipro_request_handler);
+    app
+}
+
+/// Assistant which remembers facts about the user and answers questions using them.
+#[derive(Clone)]
+pub(crate) struct OwnapiProAssistant {
+    context: Arc<OwnapiProContext>,
+}
+
+impl OwnapiProAssistant {
+    pub(crate) async fn new(config: &Config) -> anyhow::Result<Self> {
+        Ok(Self {
+            context: Arc::new(OwnapiProContext::new(config).await?),
+        })
     }
 
-    api_future.await??;
+    /// Decides if the input is data to remember, a question or a change of remembered data and handles it.
+    ///
+    /// * `session_id`: conversation session id
+    /// * `input`: data to remember or question
+    pub(crate) async fn ask(&self, session_id: &str, input: &str) -> anyhow::Result<String> {
+        let context = &self.context;
+        if let Some(moderator) = &context.moderator {
+            if let Some(result) = moderator.screen(input).await? {
+                let message = format!(
+                    "Question rejected by moderation: {}",
+                    result.flagged_categories()
+                );
+                return Err(ApiError::bad_request(message).into());
+            }
+        }
+        let session = context.sessions.get(session_id);
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(MODEL)
+            .messages([
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content(
+                        "Decide if provided iput is data to remember, a question, \
+                        a request to forget or a change of remembered data",
+                    )
+                    .build()?
+                    .into(),
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content(input)
+                    .build()?
+                    .into(),
+            ])
+            .tools(context.chat_tools.clone())
+            .build()?;
 
-    Ok(())
+        let tool_call = context
+            .openai_client
+            .chat()
+            .create(request)
+            .await?
+            .choices
+            .into_iter()
+            .find_map(|c| c.message.tool_calls)
+            .ok_or(anyhow!("{MODEL} response do not contain tool calls."))?
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("Tool calls empty"))?;
+
+        let OwnapiProResponse { reply } = context.handle(&session, tool_call).await?;
+        session.record(input, &reply);
+        Ok(reply)
+    }
 }
 
-async fn ownapipro_request_handler(
-    mut request: tide::Request<Arc<Mutex<OwnapiProContext>>>,
-) -> tide::Result {
-    let OwnapiProRequest { question } = request.body_json().await?;
-    log::debug!("Received question: {question}");
-
-    let mut context = request.state().lock().await;
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(MODEL)
-        .messages([
-            ChatCompletionRequestSystemMessageArgs::default()
-                .content("Decide if provided iput is data to remember or a question")
-                .build()?
-                .into(),
-            ChatCompletionRequestUserMessageArgs::default()
-                .content(question)
-                .build()?
-                .into(),
-        ])
-        .tools(context.chat_tools.clone())
-        .build()?;
-
-    let tool_call = context
-        .openai_client
-        .chat()
-        .create(request)
-        .await?
-        .choices
-        .into_iter()
-        .find_map(|c| c.message.tool_calls)
-        .ok_or(anyhow!("{MODEL} response do not contain tool calls."))?
-        .into_iter()
-        .next()
-        .ok_or(anyhow!("Tool calls empty"))?;
+async fn ownapipro_request_handler(mut request: tide::Request<OwnapiProAssistant>) -> tide::Result {
+    let OwnapiProRequest {
+        question,
+        session_id,
+    } = request.body_json().await?;
+    if question.trim().is_empty() {
+        return Err(ApiError::bad_request("Field 'question' must not be empty").into());
+    }
+    let session_id = session::session_id(&request, session_id.as_deref());
+    log::debug!("Received question in session '{session_id}': {question}");
 
-    let reply = context.handle(tool_call).await?;
+    let reply = OwnapiProResponse {
+        reply: request.state().ask(&session_id, &question).await?,
+    };
     log::debug!("Reply: {reply:?}");
     let response_body = tide::Body::from_json(&reply)?;
     let mut response = tide::Response::new(StatusCode::Ok);
@@ -4,13 +4,12 @@ use qdrant_client::{
     client::{Payload, QdrantClient},
     qdrant::{self, PointStruct},
 };
-use regex::Regex;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use url::Url;
 
-use crate::{aidevs, config::Config, utils};
+use crate::{aidevs, config::Config, entities::EntityExtractor, fetcher::Fetcher, utils};
 
 const QDRANT_COLLECTION: &str = "people";
 const MODEL: &str = "gpt-3.5-turbo";
@@ -107,11 +106,15 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
 
     if collection_info.vectors_count() == 0 {
         log::info!("Qdrant collection '{QDRANT_COLLECTION}' empty, filling it");
-        let people_data = get_people_data(&task_response.data).await?;
+        let fetcher = Fetcher::from_config(config)?;
+        let people_data = get_people_data(&fetcher, &task_response.data).await?;
         qdrant_fill_collection(&qdrant_client, &openai_client, people_data).await?;
     }
 
-    let fullname = find_fullname_in_question(&task_response.question)?;
+    let fullname = EntityExtractor::from_config(config)?
+        .extract_person(&task_response.question)
+        .await?
+        .full_name();
     let response =
         utils::qdrand_search(&qdrant_client, &openai_client, QDRANT_COLLECTION, &fullname).await?;
     let result = response
@@ -132,12 +135,9 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     Ok(payload)
 }
 
-async fn get_people_data(url: &Url) -> anyhow::Result<Vec<PersonInfo>> {
-    log::info!("Fetching UnkonwNews archive from {url}");
-    let response = reqwest::get(url.clone())
-        .await?
-        .json::<Vec<PersonInfo>>()
-        .await?;
+async fn get_people_data(fetcher: &Fetcher, url: &Url) -> anyhow::Result<Vec<PersonInfo>> {
+    log::info!("Fetching people data from {url}");
+    let response = fetcher.get_json::<Vec<PersonInfo>>(url.clone()).await?;
     Ok(response)
 }
 
@@ -163,23 +163,6 @@ async fn qdrant_fill_collection(
     Ok(())
 }
 
-fn find_uppercase_words(input: &str) -> Vec<&str> {
-    let re = Regex::new(r"(?m)\b[A-Z]\w*\b").unwrap();
-    re.find_iter(input).map(|m| m.as_str()).collect()
-}
-
-fn find_fullname_in_question(question: &str) -> anyhow::Result<String> {
-    let uppercase_words = find_uppercase_words(question);
-
-    let fullname = match uppercase_words.len() {
-        len if len < 2 => bail!("Can not find person fullname in question '{question}'"),
-        2 => uppercase_words.join(" "),
-        len => uppercase_words[(len - 2)..].join(" "),
-    };
-
-    Ok(fullname)
-}
-
 fn build_context_from_payload(payload: &HashMap<String, qdrant::Value>) -> String {
     let context_lines = [
         match (payload.get("name"), payload.get("surnmae")) {
@@ -2,7 +2,7 @@ use anyhow::bail;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::{aidevs, config::Config};
+use crate::{aidevs, anonymizer::PiiKind, config::Config};
 
 #[derive(Debug, Deserialize)]
 struct RodoTaskResponse {
@@ -42,9 +42,15 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         "",
         "Rulse which you have to follow are:",
         "Replace each ocuurence of your name, surname, town and occupation with provided placeholder",
-        "Placeholders: name: %imie%, surname: %nazwisko%, town: %miasto%, occupation: %zawod%."
     ];
-    let answer = answer_lines.join("\n");
+    let placeholders = format!(
+        "Placeholders: name: {}, surname: {}, town: {}, occupation: {}.",
+        PiiKind::Name.placeholder(),
+        PiiKind::Surname.placeholder(),
+        PiiKind::City.placeholder(),
+        PiiKind::Occupation.placeholder()
+    );
+    let answer = [answer_lines.join("\n"), placeholders].join("\n");
 
     let payload = json!({ "answer" : answer});
     Ok(payload)
@@ -1,13 +1,18 @@
 use anyhow::{anyhow, bail};
 use async_openai::{config::OpenAIConfig, Client};
-use reqwest::header;
-use reqwest_middleware::ClientBuilder;
-use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use reqwest::StatusCode;
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::json;
 use url::Url;
 
-use crate::{aidevs, config::Config, utils::ask_llm};
+use crate::{
+    aidevs,
+    config::Config,
+    extract::ExtractedDocument,
+    fetcher::{Fetcher, KeywordBlockDetector, StatusBlockDetector},
+    guardrails::GuardInput,
+    utils::ask_llm,
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
 const MAX_AMSWER_LENGTH: usize = 200;
@@ -25,7 +30,7 @@ struct ScraperTaskResponse {
 ///
 /// * `config`: App configuration
 /// * `token`: Task token
-pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
+pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<()> {
     let task_response = aidevs::get_task::<ScraperTaskResponse>(config, token).await?;
     log::debug!("Task API response: {task_response:#?}");
     if task_response.code != 0 {
@@ -34,7 +39,7 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     log::info!("Task message: {}", task_response.msg);
     log::info!("Task question: {}", task_response.question);
 
-    let article = download_txt(task_response.input).await?;
+    let article = download_document(config, task_response.input).await?;
 
     let context_header = [
         "Answer on my question only using data prowided after ### markers.",
@@ -43,7 +48,10 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         "###",
     ]
     .join("\n");
-    let context = format!("{context_header}\n{article}");
+    let context = match article.title {
+        Some(title) => format!("{context_header}\n# {title}\n{}", article.text),
+        None => format!("{context_header}\n{}", article.text),
+    };
     log::debug!("Context for LLM: {context}");
 
     let openai_config = OpenAIConfig::default();
@@ -53,49 +61,30 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         bail!("{MODEL} answer too long.")
     }
 
+    // Only here the article is known, so the answer is posted with it for guardrail checks
+    let input = GuardInput::new(&answer)
+        .with_question(&task_response.question)
+        .with_context(&article.text);
     let payload = json!({ "answer" : answer});
-    Ok(payload)
-}
-
-async fn download_txt(source: Url) -> anyhow::Result<String> {
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(5);
-    let client = ClientBuilder::new(reqwest::Client::new())
-        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-        .build();
-
-    log::info!("Downloading txt file from {source}");
-
-    let user_agents = [
-        "Chrome/123.0.0.0",
-        "Safari/537.36",
-        "Mozilla/5.0 (X11; Linux x86_64)",
-        "AppleWebKit/537.36 (KHTML, like Gecko)",
-    ];
-
-    for user_agent in user_agents {
-        log::debug!("Download try with user agent '{user_agent}'");
-        let response = client
-            .get(source.clone())
-            .header(header::USER_AGENT, user_agent)
-            .send()
-            .await;
+    let answer_response = aidevs::post_checked_answer(config, token, &payload, &input).await?;
+    if answer_response.code != 0 {
+        bail!(answer_response.msg)
+    }
 
-        let response = match response {
-            Ok(r) => r,
-            Err(err) => {
-                log::error!("Requeset error: {err}");
-                continue;
-            }
-        };
+    Ok(())
+}
 
-        let content = response.text().await?;
-        if content.contains("bot detected") {
-            log::debug!("Server deteced bot, trying next user agent.");
-            continue;
-        }
+async fn download_document(config: &Config, source: Url) -> anyhow::Result<ExtractedDocument> {
+    log::info!("Downloading document from {source}");
 
-        return Ok(content);
-    }
+    let fetcher = Fetcher::from_config(config)?
+        .with_detector(KeywordBlockDetector::new(["bot detected"]))
+        .with_detector(StatusBlockDetector::new([StatusCode::FORBIDDEN]));
+    let document = fetcher
+        .get_document(source.clone())
+        .await
+        .map_err(|e| anyhow!("Document download from {source} failed: {e}"))?;
+    log::debug!("Downloaded {:?} document", document.kind);
 
-    Err(anyhow!("Text download from {source} failed"))
+    Ok(document)
 }
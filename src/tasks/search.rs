@@ -9,7 +9,7 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use url::Url;
 
-use crate::{aidevs, config::Config, utils};
+use crate::{aidevs, config::Config, fetcher::Fetcher, utils};
 
 const QDRANT_COLLECTION: &str = "unknowNews";
 const UNKNOW_NEWS_ARCHIVE_URL: &str = "https://unknow.news/archiwum_aidevs.json";
@@ -92,7 +92,8 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
 
     if collection_info.vectors_count() == 0 {
         log::info!("Qdrant collection '{QDRANT_COLLECTION}' empty, filling it");
-        qdrant_fill_collection(&qdrant_client, &openai_client).await?;
+        let fetcher = Fetcher::from_config(config)?;
+        qdrant_fill_collection(&qdrant_client, &openai_client, &fetcher).await?;
     }
 
     let response = utils::qdrand_search(
@@ -117,11 +118,10 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
     Ok(payload)
 }
 
-async fn get_unknow_news_archive() -> anyhow::Result<UnknowNews> {
+async fn get_unknow_news_archive(fetcher: &Fetcher) -> anyhow::Result<UnknowNews> {
     log::info!("Fetching UnkonwNews archive from {UNKNOW_NEWS_ARCHIVE_URL}");
-    let response = reqwest::get(UNKNOW_NEWS_ARCHIVE_URL)
-        .await?
-        .json::<UnknowNews>()
+    let response = fetcher
+        .get_json::<UnknowNews>(UNKNOW_NEWS_ARCHIVE_URL)
         .await?;
     Ok(response)
 }
@@ -129,8 +129,9 @@ async fn get_unknow_news_archive() -> anyhow::Result<UnknowNews> {
 async fn qdrant_fill_collection(
     qdrant_client: &QdrantClient,
     openai_client: &Client<OpenAIConfig>,
+    fetcher: &Fetcher,
 ) -> anyhow::Result<()> {
-    let news = get_unknow_news_archive().await?.news;
+    let news = get_unknow_news_archive(fetcher).await?.news;
 
     let mut points = Vec::with_capacity(news.len());
     for (index, item) in news.into_iter().enumerate() {
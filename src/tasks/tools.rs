@@ -1,20 +1,29 @@
 use anyhow::{anyhow, bail};
-use async_openai::{
-    config::OpenAIConfig,
-    types::{
-        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs,
-    },
-    Client,
-};
-use chrono::{Local, NaiveDate};
-use serde::{Deserialize, Serialize};
+use async_openai::{config::OpenAIConfig, Client};
+use chrono::{Datelike, NaiveDate};
+use clap::Args;
+use serde::Deserialize;
 use serde_json::{json, Value};
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
 
-use crate::{aidevs, config::Config};
+use crate::{
+    aidevs,
+    config::Config,
+    organizer::{Action, Organizer},
+    temporal, utils,
+};
 
 const MODEL: &str = "gpt-3.5-turbo";
 
+/// Relative dates are resolved by `temporal` module, so LLM only copies the date expression.
+/// Dates mentioned in Polish queries are found by `temporal` too, translation is a fallback.
+const CLASSIFY_CONTEXT: &str = r#"Decide whether the task from user query should be added to the ToDo list or to the Calendar (if a time or date is provided) and return JSON object only, without any comment.
+Format: {"tool": "ToDo" or "Calendar", "desc": "task description in the query language", "when": "date expression or null"}
+"when" is the date expression copied from the query and translated to English, e.g. "tomorrow", "next friday", "in 3 days", "the day after tomorrow". Do not calculate relative dates. Write exact dates in YYYY-MM-DD format, current year is {year}.
+Examples:
+Przypomnij mi, że mam kupić mleko => {"tool": "ToDo", "desc": "Kupić mleko", "when": null}
+Jutro mam spotkanie z Marianem => {"tool": "Calendar", "desc": "Spotkanie z Marianem", "when": "tomorrow"}"#;
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct ToolsTaskResponse {
@@ -28,17 +37,27 @@ struct ToolsTaskResponse {
     question: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "tool")]
-enum Tool {
-    Calendar {
-        desc: String,
-        #[serde(with = "date_format")]
-        date: NaiveDate,
-    },
-    ToDo {
-        desc: String,
-    },
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+enum ToolKind {
+    Calendar,
+    ToDo,
+}
+
+/// Query classification by LLM.
+#[derive(Debug, Deserialize)]
+struct Classification {
+    tool: ToolKind,
+    desc: String,
+    /// Date expression normalized by `temporal::validate_date_fields`
+    when: Option<NaiveDate>,
+}
+
+#[derive(Debug, Args)]
+pub struct ToolsArgs {
+    /// Read queries from standard input and add them to the calendar or todo list,
+    /// instead of solving the task
+    #[arg(long)]
+    pub repl: bool,
 }
 
 /// The task consisted of assigning the appropriate tool (Calendar or ToDo) to the received query.
@@ -56,67 +75,88 @@ pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
         bail!("Code in response is not equal 0")
     }
 
-    let openai_config = OpenAIConfig::default();
-    let openai_client = Client::with_config(openai_config);
-
-    let today = Local::now();
-
-    let system_message = ChatCompletionRequestSystemMessageArgs::default()
-        .content(format!(
-            "{}\n{}\nToday date: {}\nExamples:\n{}\n{}",
-            task_response.msg,
-            task_response.hint,
-            today.format(date_format::FORMAT),
-            task_response.example_calendar,
-            task_response.example_todo
-        ))
-        .build()?;
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(MODEL)
-        .messages([
-            system_message.into(),
-            ChatCompletionRequestUserMessageArgs::default()
-                .content(task_response.question)
-                .build()?
-                .into(),
-        ])
-        .build()?;
-    let response = openai_client.chat().create(request).await?;
-    let answer = response
-        .choices
-        .into_iter()
-        .find_map(|c| c.message.content)
-        .ok_or(anyhow!("{MODEL} response do not contain answer."))?;
-    log::info!("{MODEL} answer: {answer}");
-
-    // Check answer format
-    let tool: Tool = serde_json::from_str(&answer)?;
-    log::debug!("Selected tool: {tool:?}");
-
-    let payload = json!({ "answer" : tool});
+    let openai_client = Client::with_config(OpenAIConfig::default());
+    let action = classify(&openai_client, &task_response.question, temporal::today()).await?;
+    log::debug!("Selected action: {action:?}");
+
+    let payload = json!({ "answer" : action});
     Ok(payload)
 }
 
-mod date_format {
-    use chrono::NaiveDate;
-    use serde::{self, Deserialize, Deserializer, Serializer};
-
-    pub const FORMAT: &str = "%Y-%m-%d";
-
-    pub fn serialize<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let s = format!("{}", date.format(FORMAT));
-        serializer.serialize_str(&s)
+/// Reads queries from standard input and executes them against calendar and todo list
+/// from configuration. `list` prints upcoming events and pending items, `exit` quits.
+///
+/// * `config`: App configuration
+pub(super) async fn repl(config: &Config) -> anyhow::Result<()> {
+    let organizer = Organizer::from_config(config);
+    let openai_client = Client::with_config(OpenAIConfig::default());
+
+    let mut stdout = io::stdout();
+    let mut lines = BufReader::new(io::stdin()).lines();
+    loop {
+        stdout.write_all(b"> ").await?;
+        stdout.flush().await?;
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+
+        let today = temporal::today();
+        match line.trim() {
+            "" => continue,
+            "exit" | "quit" => break,
+            "list" => {
+                for event in organizer.upcoming_events(today).await? {
+                    println!("{} {}", event.date, event.summary);
+                }
+                for item in organizer.pending_items().await? {
+                    println!("[ ] {}", item.desc);
+                }
+            }
+            query => {
+                let result = match classify(&openai_client, query, today).await {
+                    Ok(action) => organizer.execute(&action).await.map(|_| action),
+                    Err(err) => Err(err),
+                };
+                match result {
+                    Ok(Action::Calendar { desc, date }) => println!("Calendar: {date} {desc}"),
+                    Ok(Action::ToDo { desc }) => println!("ToDo: {desc}"),
+                    Err(err) => println!("Error: {err}"),
+                }
+            }
+        }
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        NaiveDate::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom)
-    }
+    Ok(())
+}
+
+/// Selects tool for the query with LLM and resolves the date of calendar events.
+///
+/// * `openai_client`: OpenAI client
+/// * `query`: user query, in Polish or English
+/// * `today`: date relative expressions are resolved against
+async fn classify(
+    openai_client: &Client<OpenAIConfig>,
+    query: &str,
+    today: NaiveDate,
+) -> anyhow::Result<Action> {
+    let context = CLASSIFY_CONTEXT.replace("{year}", &today.year().to_string());
+    let answer = utils::ask_llm(openai_client, MODEL, query, Some(&context)).await?;
+    let mut classification: Value = utils::parse_json_answer(&answer)?;
+    temporal::validate_date_fields(&mut classification, &["when"], query, today)?;
+    let classification: Classification = serde_json::from_value(classification)?;
+
+    let action = match classification.tool {
+        ToolKind::ToDo => Action::ToDo {
+            desc: classification.desc,
+        },
+        ToolKind::Calendar => Action::Calendar {
+            desc: classification.desc,
+            date: classification
+                .when
+                .or_else(|| temporal::find_dates(query, today).first().map(|m| m.date))
+                .ok_or(anyhow!("Date not found in query: {query}"))?,
+        },
+    };
+
+    Ok(action)
 }
@@ -1,9 +1,13 @@
 use anyhow::bail;
-use async_openai::{config::OpenAIConfig, Client};
+use futures::{future::BoxFuture, FutureExt};
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::{aidevs, config::Config, utils::ask_llm};
+use crate::{
+    aidevs,
+    config::Config,
+    evidence::{ClueSource, EvidenceEngine},
+};
 
 const MODEL: &str = "gpt-4";
 
@@ -15,52 +19,50 @@ struct WhoAmITaskResponse {
     msg: String,
 }
 
+/// Task endpoint returning a random hint about the person on each call.
+struct TaskHints<'a> {
+    config: &'a Config,
+    token: &'a str,
+}
+
 /// The task consisted of guessing the first and last name of a person based on hints received from the AI_Devs API.
 /// Each query to this API returned a different hint.
 ///
 /// * `config`: App configuration
 /// * `token`: Task token
 pub(super) async fn run(config: &Config, token: &str) -> anyhow::Result<Value> {
-    let question = "Who is being talked about?";
-    let context_header = [
-        "Answer on my question using data prowided after ### markers and your base knowledge",
-        "Answer concisely as possible",
-        "If you do not know the persons name and surname reply only with 'Not enough data'",
-        "",
-        "###",
-    ];
+    let question = "Who is being talked about? Answer with the person's name and surname only.";
+    let hints = TaskHints { config, token };
 
-    let mut context = context_header.join("\n");
+    let conclusion = EvidenceEngine::from_config(config, MODEL)
+        .gather(question, &hints)
+        .await?;
+    log::info!(
+        "Answer after {} iterations and {} hints: {}",
+        conclusion.iterations,
+        conclusion.clues.len(),
+        conclusion.candidate.reasoning
+    );
 
-    let openai_config = OpenAIConfig::default();
-    let client = Client::with_config(openai_config);
-
-    loop {
-        let hint = get_next_hint(config, token).await?;
-        if context.contains(&hint) {
-            continue;
-        }
+    let payload = json!({ "answer" : conclusion.candidate.answer});
+    Ok(payload)
+}
 
-        context.push_str(&hint);
+impl TaskHints<'_> {
+    async fn get_next_hint(&self) -> anyhow::Result<String> {
+        let task_response = aidevs::get_task::<WhoAmITaskResponse>(self.config, self.token).await?;
+        log::debug!("Task API response: {task_response:#?}");
 
-        let answer = ask_llm(&client, MODEL, question, Some(&context)).await?;
-        if answer.contains("Not enough data") {
-            log::info!("Not enough data, fetching next hint");
-            continue;
+        if task_response.code != 0 {
+            bail!("Code in response is not equal 0")
         }
 
-        let payload = json!({ "answer" : answer});
-        return Ok(payload);
+        Ok(task_response.hint)
     }
 }
 
-async fn get_next_hint(config: &Config, token: &str) -> anyhow::Result<String> {
-    let task_response = aidevs::get_task::<WhoAmITaskResponse>(config, token).await?;
-    log::debug!("Task API response: {task_response:#?}");
-
-    if task_response.code != 0 {
-        bail!("Code in response is not equal 0")
+impl ClueSource for TaskHints<'_> {
+    fn next_clue(&self) -> BoxFuture<'_, anyhow::Result<String>> {
+        self.get_next_hint().boxed()
     }
-
-    Ok(task_response.hint)
 }
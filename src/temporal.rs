@@ -0,0 +1,395 @@
+use anyhow::bail;
+use chrono::{Datelike, Days, Local, Months, NaiveDate, Weekday};
+use serde_json::Value;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// Longest expression in words, e.g. "w przyszły piątek" or "a month from now".
+const MAX_EXPRESSION_WORDS: usize = 4;
+
+/// Unit of relative date offset, e.g. "in 3 weeks".
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Date expression found in a text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateMention {
+    pub expression: String,
+    pub date: NaiveDate,
+}
+
+/// Current local date.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Resolves date expression in Polish or English relative to `today`. Supported expressions:
+/// - exact date in YYYY-MM-DD format,
+/// - "today", "tomorrow", "the day after tomorrow", "yesterday" and Polish "dziś", "jutro", "pojutrze"...,
+/// - weekday names, optionally preceded with "on", "this", "next", e.g. "w przyszły poniedziałek",
+/// - "next week", "next month", "next year", "w przyszłym tygodniu"...,
+/// - offsets like "in 3 days", "in two weeks", "a month from now", "za tydzień", "za dwa dni".
+///
+/// "next friday" and bare "friday" mean the first Friday after today, "this friday" may be today.
+///
+/// * `expression`: date expression
+/// * `today`: date the expression is relative to
+pub fn resolve_date(expression: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let expression = expression
+        .trim()
+        .trim_end_matches(['.', ',', '!', '?'])
+        .to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&expression, DATE_FORMAT) {
+        return Some(date);
+    }
+
+    let words = expression
+        .split_whitespace()
+        .filter(|w| !matches!(*w, "on" | "the" | "w" | "we"))
+        .collect::<Vec<_>>();
+
+    match words.as_slice() {
+        ["today" | "tonight" | "dziś" | "dzisiaj"] => Some(today),
+        ["tomorrow" | "jutro"] => today.succ_opt(),
+        ["day", "after", "tomorrow"] | ["pojutrze"] => today.checked_add_days(Days::new(2)),
+        ["yesterday" | "wczoraj"] => today.pred_opt(),
+        ["day", "before", "yesterday"] | ["przedwczoraj"] => today.checked_sub_days(Days::new(2)),
+        [next, word] if is_next(next) => match parse_unit(word) {
+            Some(unit) => shift(today, 1, unit),
+            None => Some(next_weekday(today, parse_weekday(word)?, false)),
+        },
+        ["this" | "ten" | "ta" | "tę" | "tą", weekday] => {
+            Some(next_weekday(today, parse_weekday(weekday)?, true))
+        }
+        [weekday] => Some(next_weekday(today, parse_weekday(weekday)?, false)),
+        ["in" | "za", unit] => shift(today, 1, parse_unit(unit)?),
+        ["in" | "za" | "after", count, unit] | [count, unit, "from", "now"] => {
+            shift(today, parse_number(count)?, parse_unit(unit)?)
+        }
+        _ => None,
+    }
+}
+
+/// Finds date expressions in the text, longest expressions first, without overlapping.
+///
+/// * `text`: text in Polish or English
+/// * `today`: date the expressions are relative to
+pub fn find_dates(text: &str, today: NaiveDate) -> Vec<DateMention> {
+    let words = text
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut mentions = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let longest = (start + MAX_EXPRESSION_WORDS).min(words.len());
+        let found = (start + 1..=longest).rev().find_map(|end| {
+            let expression = words[start..end].join(" ");
+            resolve_date(&expression, today).map(|date| (end, DateMention { expression, date }))
+        });
+        match found {
+            Some((end, mention)) => {
+                mentions.push(mention);
+                start = end;
+            }
+            None => start += 1,
+        }
+    }
+
+    mentions
+}
+
+/// Describes today and dates mentioned in the text, to be added to LLM context
+/// instead of letting the model compute dates.
+///
+/// * `text`: user input
+/// * `today`: current date
+pub fn date_context(text: &str, today: NaiveDate) -> String {
+    let mut context = format!("Today is {}, {today}", weekday_name(today));
+    for mention in find_dates(text, today) {
+        context.push_str(&format!(
+            "\n'{}' is {}, {}",
+            mention.expression,
+            weekday_name(mention.date),
+            mention.date
+        ));
+    }
+    context
+}
+
+/// Validates date fields of structured LLM answer and normalizes them to YYYY-MM-DD format.
+/// A field quoting a date expression of the source text gets the date of that mention.
+/// Fields which can not be resolved get the date of the only mention in the source, if there is one.
+/// Fields are searched in nested objects and arrays too, `null` fields are left untouched.
+///
+/// * `answer`: structured LLM answer
+/// * `fields`: names of date fields
+/// * `source`: text the answer was generated from
+/// * `today`: date relative expressions are resolved against
+pub fn validate_date_fields(
+    answer: &mut Value,
+    fields: &[&str],
+    source: &str,
+    today: NaiveDate,
+) -> anyhow::Result<()> {
+    let mentions = find_dates(source, today);
+    validate_value(answer, fields, &mentions, today)
+}
+
+fn validate_value(
+    value: &mut Value,
+    fields: &[&str],
+    mentions: &[DateMention],
+    today: NaiveDate,
+) -> anyhow::Result<()> {
+    match value {
+        Value::Object(object) => {
+            for (name, field) in object.iter_mut() {
+                match (field, fields.contains(&name.as_str())) {
+                    (Value::String(text), true) => {
+                        let resolved = resolve_date(text, today);
+                        let date = match (quoted_mention(text, mentions), resolved, mentions) {
+                            (Some(mention), _, _) => mention.date,
+                            (None, Some(resolved), _) => resolved,
+                            (None, None, [mention]) => mention.date,
+                            (None, None, _) => bail!("Invalid date '{text}' in field '{name}'"),
+                        };
+                        if resolved != Some(date) {
+                            log::warn!("Date '{text}' in field '{name}' corrected to {date}");
+                        }
+                        *text = date.format(DATE_FORMAT).to_string();
+                    }
+                    (field, _) => validate_value(field, fields, mentions, today)?,
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                validate_value(value, fields, mentions, today)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Mention of the source text which the field value was taken from, e.g. `piątek` from `w piątek`.
+/// Exact match wins, otherwise the longest mention containing or contained in the value,
+/// so `tomorrow` is not taken from `the day after tomorrow`.
+fn quoted_mention<'a>(text: &str, mentions: &'a [DateMention]) -> Option<&'a DateMention> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return None;
+    }
+    mentions
+        .iter()
+        .find(|mention| mention.expression.to_lowercase() == text)
+        .or_else(|| {
+            mentions
+                .iter()
+                .filter(|mention| {
+                    let expression = mention.expression.to_lowercase();
+                    expression.contains(&text) || text.contains(&expression)
+                })
+                .max_by_key(|mention| mention.expression.len())
+        })
+}
+
+pub fn weekday_name(date: NaiveDate) -> &'static str {
+    match date.weekday() {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// First given weekday after the date, or the date itself when `inclusive`.
+fn next_weekday(date: NaiveDate, weekday: Weekday, inclusive: bool) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    let days = match days == 0 && !inclusive {
+        true => 7,
+        false => days,
+    };
+    date + Days::new(days.into())
+}
+
+fn shift(date: NaiveDate, count: u32, unit: Unit) -> Option<NaiveDate> {
+    match unit {
+        Unit::Day => date.checked_add_days(Days::new(count.into())),
+        Unit::Week => date.checked_add_days(Days::new(u64::from(count) * 7)),
+        Unit::Month => date.checked_add_months(Months::new(count)),
+        Unit::Year => date.checked_add_months(Months::new(count * 12)),
+    }
+}
+
+fn is_next(word: &str) -> bool {
+    matches!(
+        word,
+        "next"
+            | "coming"
+            | "przyszły"
+            | "przyszła"
+            | "przyszłą"
+            | "przyszłym"
+            | "następny"
+            | "następna"
+            | "następną"
+            | "następnym"
+            | "najbliższy"
+            | "najbliższa"
+            | "najbliższą"
+            | "najbliższym"
+    )
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" | "poniedziałek" => Weekday::Mon,
+        "tuesday" | "wtorek" => Weekday::Tue,
+        "wednesday" | "środa" | "środę" => Weekday::Wed,
+        "thursday" | "czwartek" => Weekday::Thu,
+        "friday" | "piątek" => Weekday::Fri,
+        "saturday" | "sobota" | "sobotę" => Weekday::Sat,
+        "sunday" | "niedziela" | "niedzielę" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn parse_unit(word: &str) -> Option<Unit> {
+    let unit = match word {
+        "day" | "days" | "dzień" | "dni" | "dnia" => Unit::Day,
+        "week" | "weeks" | "tydzień" | "tygodnie" | "tygodni" | "tygodniu" => Unit::Week,
+        "month" | "months" | "miesiąc" | "miesiące" | "miesięcy" | "miesiącu" => Unit::Month,
+        "year" | "years" | "rok" | "lata" | "lat" | "roku" => Unit::Year,
+        _ => return None,
+    };
+    Some(unit)
+}
+
+fn parse_number(word: &str) -> Option<u32> {
+    let number = match word {
+        "a" | "an" | "one" | "jeden" | "jedna" => 1,
+        "two" | "dwa" | "dwie" => 2,
+        "three" | "trzy" => 3,
+        "four" | "cztery" => 4,
+        "five" | "pięć" => 5,
+        "six" | "sześć" => 6,
+        "seven" | "siedem" => 7,
+        "eight" | "osiem" => 8,
+        "nine" | "dziewięć" => 9,
+        "ten" | "dziesięć" => 10,
+        _ => return word.parse().ok(),
+    };
+    Some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_date() {
+        // Wednesday
+        let today = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(resolve_date("2024-05-01", today), date(2024, 5, 1));
+        assert_eq!(resolve_date("Today", today), date(2024, 1, 31));
+        assert_eq!(resolve_date("tomorrow", today), date(2024, 2, 1));
+        assert_eq!(
+            resolve_date("the day after tomorrow", today),
+            date(2024, 2, 2)
+        );
+        assert_eq!(resolve_date("next Friday", today), date(2024, 2, 2));
+        assert_eq!(resolve_date("on wednesday", today), date(2024, 2, 7));
+        assert_eq!(resolve_date("this wednesday", today), date(2024, 1, 31));
+        assert_eq!(resolve_date("in 3 days", today), date(2024, 2, 3));
+        assert_eq!(resolve_date("in two weeks", today), date(2024, 2, 14));
+        assert_eq!(resolve_date("a month from now", today), date(2024, 2, 29));
+        assert_eq!(resolve_date("next year", today), date(2025, 1, 31));
+        assert_eq!(resolve_date("someday", today), None);
+
+        assert_eq!(resolve_date("Jutro", today), date(2024, 2, 1));
+        assert_eq!(resolve_date("pojutrze", today), date(2024, 2, 2));
+        assert_eq!(
+            resolve_date("w przyszły poniedziałek", today),
+            date(2024, 2, 5)
+        );
+        assert_eq!(resolve_date("w tę środę", today), date(2024, 1, 31));
+        assert_eq!(resolve_date("za tydzień", today), date(2024, 2, 7));
+        assert_eq!(resolve_date("za dwa dni", today), date(2024, 2, 2));
+        assert_eq!(
+            resolve_date("w przyszłym miesiącu", today),
+            date(2024, 2, 29)
+        );
+    }
+
+    #[test]
+    fn test_validate_date_fields() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+
+        let mentions = find_dates("Przypomnij mi jutro, że w piątek mam spotkanie.", today);
+        assert_eq!(
+            mentions,
+            vec![
+                DateMention {
+                    expression: "jutro".into(),
+                    date: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()
+                },
+                DateMention {
+                    expression: "w piątek".into(),
+                    date: NaiveDate::from_ymd_opt(2024, 2, 2).unwrap()
+                },
+            ]
+        );
+
+        // Each field gets the date of the mention it quotes
+        let source = "Przypomnij mi jutro, że w piątek mam spotkanie.";
+        let mut answer = json!({"remind": "jutro", "date": "piątek", "created": "2024-01-30"});
+        validate_date_fields(&mut answer, &["remind", "date", "created"], source, today).unwrap();
+        assert_eq!(
+            answer,
+            json!({"remind": "2024-02-01", "date": "2024-02-02", "created": "2024-01-30"})
+        );
+
+        // Exact mention wins over an earlier one containing the value
+        let source = "The day after tomorrow I fly to Paris, tomorrow I pack.";
+        let mut answer = json!({"flight": "the day after tomorrow", "packing": "Tomorrow"});
+        validate_date_fields(&mut answer, &["flight", "packing"], source, today).unwrap();
+        assert_eq!(
+            answer,
+            json!({"flight": "2024-02-02", "packing": "2024-02-01"})
+        );
+
+        // Unresolvable field gets the date of the only mention
+        let mut answer = json!({"tool": "Calendar", "date": "on the meeting day"});
+        validate_date_fields(&mut answer, &["date"], "W piątek mam spotkanie", today).unwrap();
+        assert_eq!(answer["date"], "2024-02-02");
+        let mut answer = json!({"date": "on the meeting day"});
+        assert!(validate_date_fields(&mut answer, &["date"], source, today).is_err());
+
+        let mut answer = json!({"events": [{"date": "next monday"}, {"date": null}]});
+        validate_date_fields(&mut answer, &["date"], "", today).unwrap();
+        assert_eq!(
+            answer,
+            json!({"events": [{"date": "2024-02-05"}, {"date": null}]})
+        );
+
+        let mut answer = json!({"date": "soon"});
+        assert!(validate_date_fields(&mut answer, &["date"], "", today).is_err());
+    }
+}
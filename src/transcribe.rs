@@ -0,0 +1,330 @@
+mod audio;
+mod local;
+mod openai;
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::anyhow;
+use clap::{Args, ValueEnum};
+use futures::future::BoxFuture;
+use strum_macros::{Display, EnumString};
+use url::Url;
+
+use crate::{config::Config, fetcher::Fetcher};
+
+pub use local::WhisperCppTranscriber;
+pub use openai::OpenAiTranscriber;
+
+/// Tail of each chunk searched for silence to split on.
+const CHUNK_SPLIT_SEARCH: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum TranscriptionBackendKind {
+    /// OpenAI `whisper-1` API
+    Openai,
+    /// whisper.cpp command line tool
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum TranscriptFormat {
+    Text,
+    Srt,
+    Vtt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub segments: Vec<Segment>,
+}
+
+/// Speech to text model.
+pub trait TranscriptionBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Transcribes 16 kHz mono WAV file. Segment times are relative to the file start.
+    ///
+    /// * `wav`: WAV file content
+    /// * `language`: ISO-639-1 language code, detected when not provided
+    fn transcribe<'a>(
+        &'a self,
+        wav: Vec<u8>,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<Segment>>>;
+}
+
+/// Transcribes audio of any length. Audio is decoded and split into chunks which fit
+/// the backend limits, chunk transcriptions are stitched using chunk start times.
+pub struct Transcriber {
+    backend: Box<dyn TranscriptionBackend>,
+    chunk_length: Duration,
+}
+
+#[derive(Debug, Args)]
+pub struct TranscribeArgs {
+    /// Audio file path or URL
+    pub source: String,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = TranscriptFormat::Text)]
+    pub format: TranscriptFormat,
+
+    /// Audio language (ISO-639-1 code), detected when not provided
+    #[arg(short, long)]
+    pub language: Option<String>,
+
+    /// Write transcript to the file instead of standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+impl Transcript {
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn to_srt(&self) -> String {
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(index, s)| {
+                format!(
+                    "{}\n{} --> {}\n{}\n",
+                    index + 1,
+                    format_timestamp(s.start, ','),
+                    format_timestamp(s.end, ','),
+                    s.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn to_vtt(&self) -> String {
+        let cues = self
+            .segments
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} --> {}\n{}\n",
+                    format_timestamp(s.start, '.'),
+                    format_timestamp(s.end, '.'),
+                    s.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("WEBVTT\n\n{cues}")
+    }
+
+    pub fn format(&self, format: TranscriptFormat) -> String {
+        match format {
+            TranscriptFormat::Text => self.text(),
+            TranscriptFormat::Srt => self.to_srt(),
+            TranscriptFormat::Vtt => self.to_vtt(),
+        }
+    }
+}
+
+impl Transcriber {
+    pub fn new(backend: Box<dyn TranscriptionBackend>, chunk_length: Duration) -> Self {
+        Self {
+            backend,
+            chunk_length,
+        }
+    }
+
+    /// Creates transcriber with backend selected in configuration.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let backend: Box<dyn TranscriptionBackend> = match config.transcription_backend {
+            TranscriptionBackendKind::Openai => Box::new(OpenAiTranscriber::from_config(config)?),
+            TranscriptionBackendKind::Local => {
+                let model = config
+                    .whisper_cpp_model
+                    .clone()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .ok_or(anyhow!("whisper.cpp model path not found in configuration"))?;
+                Box::new(WhisperCppTranscriber::new(
+                    &config.whisper_cpp_binary,
+                    model,
+                    config.whisper_cpp_threads,
+                ))
+            }
+        };
+        log::info!("Using '{}' transcription backend", backend.name());
+
+        Ok(Self::new(
+            backend,
+            Duration::from_secs(config.transcription_chunk_secs),
+        ))
+    }
+
+    /// Transcribes audio file.
+    ///
+    /// * `bytes`: audio file content (MP3, WAV, OGG Vorbis or FLAC)
+    /// * `extension`: file extension used as format hint
+    /// * `language`: ISO-639-1 language code, detected when not provided
+    pub async fn transcribe(
+        &self,
+        bytes: Vec<u8>,
+        extension: Option<&str>,
+        language: Option<&str>,
+    ) -> anyhow::Result<Transcript> {
+        let extension = extension.map(String::from);
+        let samples =
+            tokio::task::spawn_blocking(move || audio::decode(bytes, extension.as_deref()))
+                .await??;
+
+        let sample_rate = audio::SAMPLE_RATE as f64;
+        let max_samples = (self.chunk_length.as_secs_f64() * sample_rate) as usize;
+        let search_samples = (CHUNK_SPLIT_SEARCH.as_secs_f64() * sample_rate) as usize;
+        let chunks = audio::split_chunks(&samples, max_samples, search_samples);
+        log::info!(
+            "Transcribing {:.1} s of audio in {} chunk(s)",
+            samples.len() as f64 / sample_rate,
+            chunks.len()
+        );
+
+        let mut transcript = Transcript::default();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let offset = chunk.start as f64 / sample_rate;
+            let chunk_duration = chunk.len() as f64 / sample_rate;
+            log::debug!("Transcribing chunk {index} starting at {offset:.1} s");
+
+            let wav = audio::encode_wav(&samples[chunk])?;
+            let segments = self.backend.transcribe(wav, language).await?;
+            for segment in segments {
+                // Backends without timestamps return zero length segment for the whole chunk
+                let end = match segment.end > segment.start {
+                    true => segment.end,
+                    false => chunk_duration,
+                };
+                transcript.segments.push(Segment {
+                    start: offset + segment.start,
+                    end: offset + end,
+                    text: segment.text,
+                });
+            }
+        }
+
+        Ok(transcript)
+    }
+
+    /// Downloads audio file from URL and transcribes it.
+    ///
+    /// * `fetcher`: HTTP client
+    /// * `url`: audio file URL
+    /// * `language`: ISO-639-1 language code, detected when not provided
+    pub async fn transcribe_url(
+        &self,
+        fetcher: &Fetcher,
+        url: Url,
+        language: Option<&str>,
+    ) -> anyhow::Result<Transcript> {
+        let extension = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_lowercase());
+
+        log::debug!("Downloading {url}");
+        let response = fetcher.get(url).await?;
+
+        self.transcribe(response.body, extension.as_deref(), language)
+            .await
+    }
+}
+
+/// Transcribes audio file or URL and prints or saves the transcript.
+///
+/// * `config`: App configuration
+/// * `args`: transcribe command arguments
+pub async fn run(config: &Config, args: TranscribeArgs) -> anyhow::Result<()> {
+    let transcriber = Transcriber::from_config(config)?;
+    let language = args.language.as_deref();
+
+    let transcript = match Url::parse(&args.source) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {
+            let fetcher = Fetcher::from_config(config)?;
+            transcriber.transcribe_url(&fetcher, url, language).await?
+        }
+        _ => {
+            let path = PathBuf::from(&args.source);
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+            let bytes = std::fs::read(&path)
+                .map_err(|e| anyhow!("Can not read {}: {e}", path.display()))?;
+            transcriber
+                .transcribe(bytes, extension.as_deref(), language)
+                .await?
+        }
+    };
+
+    let output = transcript.format(args.format);
+    match args.output {
+        Some(path) => std::fs::write(path, output)?,
+        None => println!("{output}"),
+    }
+
+    Ok(())
+}
+
+/// Formats seconds as `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (VTT).
+fn format_timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_formats() {
+        let transcript = Transcript {
+            segments: vec![
+                Segment {
+                    start: 0.0,
+                    end: 2.5,
+                    text: "Hello".into(),
+                },
+                Segment {
+                    start: 3661.25,
+                    end: 3663.0,
+                    text: "world".into(),
+                },
+            ],
+        };
+
+        assert_eq!(transcript.text(), "Hello world");
+        assert_eq!(
+            transcript.to_srt(),
+            "1\n00:00:00,000 --> 00:00:02,500\nHello\n\n2\n01:01:01,250 --> 01:01:03,000\nworld\n"
+        );
+        assert!(transcript
+            .to_vtt()
+            .starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.500\nHello\n"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::{io::Cursor, ops::Range};
+
+use anyhow::anyhow;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError,
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Sample rate expected by Whisper models.
+pub const SAMPLE_RATE: u32 = 16_000;
+/// Length of the window in which loudness is measured when looking for chunk boundary (100 ms).
+const ENERGY_WINDOW: usize = SAMPLE_RATE as usize / 10;
+
+/// Decodes audio file (MP3, WAV, OGG Vorbis, FLAC) to mono samples with Whisper sample rate.
+///
+/// * `bytes`: audio file content
+/// * `extension`: file extension used as format hint
+pub fn decode(bytes: Vec<u8>, extension: Option<&str>) -> anyhow::Result<Vec<f32>> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or(anyhow!("Audio file does not contain any track"))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or(anyhow!("Audio sample rate unknown"))?;
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut mono = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(err)) => {
+                log::debug!("Skipping corrupted audio packet: {err}");
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        mono.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    Ok(resample(&mono, sample_rate, SAMPLE_RATE))
+}
+
+/// Encodes samples as 16-bit mono WAV file.
+///
+/// * `samples`: mono samples with Whisper sample rate
+pub fn encode_wav(samples: &[f32]) -> anyhow::Result<Vec<u8>> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut output = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut output, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(output.into_inner())
+}
+
+/// Splits audio to chunks not longer than `max_samples`. Every chunk ends in the quietest part
+/// of its last `search_samples`, so words are rarely cut in half.
+///
+/// * `samples`: mono samples
+/// * `max_samples`: maximal chunk length
+/// * `search_samples`: length of the chunk tail searched for silence
+pub fn split_chunks(
+    samples: &[f32],
+    max_samples: usize,
+    search_samples: usize,
+) -> Vec<Range<usize>> {
+    let max_samples = max_samples.max(2 * ENERGY_WINDOW);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while samples.len() - start > max_samples {
+        let limit = start + max_samples;
+        let search_start = limit
+            .saturating_sub(search_samples)
+            .max(start + ENERGY_WINDOW);
+        let end = (search_start..=limit - ENERGY_WINDOW)
+            .step_by(ENERGY_WINDOW / 4)
+            .map(|w| (energy(&samples[w..w + ENERGY_WINDOW]), w))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, w)| w + ENERGY_WINDOW / 2)
+            .unwrap_or(limit);
+
+        chunks.push(start..end);
+        start = end;
+    }
+    if start < samples.len() {
+        chunks.push(start..samples.len());
+    }
+
+    chunks
+}
+
+fn energy(samples: &[f32]) -> f32 {
+    samples.iter().map(|s| s * s).sum()
+}
+
+/// Changes sample rate. Samples are averaged when downsampling, which is good enough for speech.
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from as f64 / to as f64;
+    let length = (samples.len() as f64 / ratio) as usize;
+    (0..length)
+        .map(|i| {
+            let start = ((i as f64 * ratio) as usize).min(samples.len() - 1);
+            let end = (((i + 1) as f64 * ratio) as usize).clamp(start + 1, samples.len());
+            samples[start..end].iter().sum::<f32>() / (end - start) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_chunks_at_silence() {
+        let second = SAMPLE_RATE as usize;
+        // 3 s of tone with silence between 1.5 s and 1.7 s
+        let samples = (0..3 * second)
+            .map(|i| {
+                if (second * 3 / 2..second * 17 / 10).contains(&i) {
+                    0.0
+                } else {
+                    (i as f32 / 10.0).sin() * 0.5
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let chunks = split_chunks(&samples, 2 * second, second);
+        assert_eq!(chunks.len(), 2);
+        assert!((second * 3 / 2..second * 17 / 10).contains(&chunks[0].end));
+        assert_eq!(chunks[1], chunks[0].end..samples.len());
+    }
+
+    #[test]
+    fn test_wav_roundtrip() {
+        let samples = (0..SAMPLE_RATE)
+            .map(|i| (i as f32 / 20.0).sin() * 0.25)
+            .collect::<Vec<_>>();
+
+        let wav = encode_wav(&samples).unwrap();
+        let decoded = decode(wav, Some("wav")).unwrap();
+        assert_eq!(decoded.len(), samples.len());
+        assert!((decoded[100] - samples[100]).abs() < 0.001);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{path::PathBuf, process::Stdio};
+
+use anyhow::{anyhow, bail};
+use futures::{future::BoxFuture, FutureExt};
+use serde::Deserialize;
+use tempfile::tempdir;
+use tokio::process::Command;
+
+use super::{Segment, TranscriptionBackend};
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppOutput {
+    transcription: Vec<WhisperCppSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhisperCppSegment {
+    offsets: WhisperCppOffsets,
+    text: String,
+}
+
+/// Segment boundaries in milliseconds.
+#[derive(Debug, Deserialize)]
+struct WhisperCppOffsets {
+    from: u64,
+    to: u64,
+}
+
+/// Transcription on CPU with whisper.cpp command line tool and a local GGML model.
+pub struct WhisperCppTranscriber {
+    binary: String,
+    model: PathBuf,
+    threads: Option<usize>,
+}
+
+impl WhisperCppTranscriber {
+    pub fn new(binary: &str, model: PathBuf, threads: Option<usize>) -> Self {
+        Self {
+            binary: binary.to_string(),
+            model,
+            threads,
+        }
+    }
+
+    async fn run(&self, wav: Vec<u8>, language: Option<&str>) -> anyhow::Result<Vec<Segment>> {
+        let work_dir = tempdir()?;
+        let input_path = work_dir.path().join("audio.wav");
+        let output_prefix = work_dir.path().join("transcription");
+        std::fs::write(&input_path, wav)?;
+
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--file")
+            .arg(&input_path)
+            .arg("--language")
+            .arg(language.unwrap_or("auto"))
+            .arg("--output-json")
+            .arg("--output-file")
+            .arg(&output_prefix)
+            .arg("--no-prints")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        if let Some(threads) = self.threads {
+            command.arg("--threads").arg(threads.to_string());
+        }
+
+        log::debug!(
+            "Running {} with model {}",
+            self.binary,
+            self.model.display()
+        );
+        let output = command
+            .output()
+            .await
+            .map_err(|e| anyhow!("Can not start '{}': {e}", self.binary))?;
+        if !output.status.success() {
+            bail!(
+                "{} failed ({}): {}",
+                self.binary,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let json = std::fs::read_to_string(output_prefix.with_extension("json"))?;
+        let output: WhisperCppOutput = serde_json::from_str(&json)?;
+        let segments = output
+            .transcription
+            .into_iter()
+            .map(|s| Segment {
+                start: s.offsets.from as f64 / 1000.0,
+                end: s.offsets.to as f64 / 1000.0,
+                text: s.text.trim().to_string(),
+            })
+            .collect();
+
+        Ok(segments)
+    }
+}
+
+impl TranscriptionBackend for WhisperCppTranscriber {
+    fn name(&self) -> &'static str {
+        "whisper.cpp"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        wav: Vec<u8>,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<Segment>>> {
+        self.run(wav, language).boxed()
+    }
+}
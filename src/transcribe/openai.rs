@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use async_openai::config::{Config as _, OpenAIConfig};
+use futures::{future::BoxFuture, FutureExt};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+
+use super::{Segment, TranscriptionBackend};
+use crate::{
+    config::Config,
+    fetcher::{Fetcher, FetcherOptions},
+};
+
+const MODEL: &str = "whisper-1";
+/// Transcription of a long chunk takes much longer than a regular request.
+const MIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Deserialize)]
+struct VerboseTranscription {
+    text: String,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+/// Transcription with OpenAI `whisper-1` model. The `verbose_json` response format
+/// is not supported by `async-openai`, so the API is called directly.
+pub struct OpenAiTranscriber {
+    fetcher: Fetcher,
+    config: OpenAIConfig,
+}
+
+impl OpenAiTranscriber {
+    pub fn new(fetcher: Fetcher) -> Self {
+        Self {
+            fetcher,
+            config: OpenAIConfig::default(),
+        }
+    }
+
+    /// Creates transcriber with shared fetcher settings and timeout long enough for transcription.
+    ///
+    /// * `config`: App configuration
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut options = FetcherOptions::from(config);
+        options.timeout = options.timeout.max(MIN_TIMEOUT);
+        Ok(Self::new(Fetcher::new(options)?))
+    }
+
+    async fn request(&self, wav: Vec<u8>, language: Option<&str>) -> anyhow::Result<Vec<Segment>> {
+        let file = Part::bytes(wav)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")?;
+        let mut form = Form::new()
+            .part("file", file)
+            .text("model", MODEL)
+            .text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "segment");
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+
+        let transcription: VerboseTranscription = self
+            .fetcher
+            .post_multipart_with_headers(
+                self.config.url("/audio/transcriptions"),
+                self.config.headers(),
+                form,
+            )
+            .await?;
+        if transcription.segments.is_empty() {
+            return Ok(vec![Segment {
+                start: 0.0,
+                end: 0.0,
+                text: transcription.text.trim().to_string(),
+            }]);
+        }
+
+        let segments = transcription
+            .segments
+            .into_iter()
+            .map(|s| Segment {
+                start: s.start,
+                end: s.end,
+                text: s.text.trim().to_string(),
+            })
+            .collect();
+        Ok(segments)
+    }
+}
+
+impl TranscriptionBackend for OpenAiTranscriber {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        wav: Vec<u8>,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<Segment>>> {
+        self.request(wav, language).boxed()
+    }
+}
@@ -0,0 +1,73 @@
+mod local;
+mod process;
+
+use anyhow::{anyhow, bail};
+use futures::future::BoxFuture;
+use strum_macros::{Display, EnumString};
+use url::Url;
+
+use crate::config::Config;
+
+pub use local::{LocalTunnel, StaticTunnel};
+pub use process::{ProcessTunnel, ProcessTunnelKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum TunnelBackend {
+    /// Public URL provided in configuration, tunnel started manually
+    Static,
+    /// No tunnel, server available only locally (e.g. for the mock task server)
+    Local,
+    Ngrok,
+    Cloudflared,
+    Ssh,
+}
+
+/// Exposes local API server under a public URL.
+pub trait TunnelProvider: Send {
+    fn name(&self) -> &'static str;
+
+    /// Opens tunnel to the local address and returns its public base URL.
+    ///
+    /// * `local_address`: address the API server listens on, e.g. `localhost:8080`
+    fn open<'a>(&'a mut self, local_address: &'a str) -> BoxFuture<'a, anyhow::Result<Url>>;
+
+    fn close(&mut self) -> BoxFuture<'_, anyhow::Result<()>>;
+}
+
+/// Creates tunnel provider selected in configuration.
+/// When backend is not set explicitly, static tunnel is used if `API_TUNNEL_URL` is present.
+///
+/// * `config`: App configuration
+pub fn from_config(config: &Config) -> anyhow::Result<Box<dyn TunnelProvider>> {
+    let backend = match config.tunnel_provider {
+        Some(backend) => backend,
+        None if config.api_tunnel_url.is_some() => TunnelBackend::Static,
+        None => bail!("Tunnel not configured, set TUNNEL_PROVIDER or API_TUNNEL_URL"),
+    };
+    log::info!("Using '{backend}' tunnel provider");
+
+    let binary = config.tunnel_binary.clone().filter(|b| !b.is_empty());
+    let provider: Box<dyn TunnelProvider> = match backend {
+        TunnelBackend::Static => {
+            let url = config
+                .api_tunnel_url
+                .as_ref()
+                .ok_or(anyhow!("Tunnel URL not found in configuration"))?;
+            Box::new(StaticTunnel::new(url.clone()))
+        }
+        TunnelBackend::Local => Box::new(LocalTunnel),
+        TunnelBackend::Ngrok => Box::new(ProcessTunnel::new(ProcessTunnelKind::Ngrok, binary)),
+        TunnelBackend::Cloudflared => {
+            Box::new(ProcessTunnel::new(ProcessTunnelKind::Cloudflared, binary))
+        }
+        TunnelBackend::Ssh => Box::new(ProcessTunnel::new(
+            ProcessTunnelKind::Ssh {
+                host: config.tunnel_ssh_host.clone(),
+            },
+            binary,
+        )),
+    };
+
+    Ok(provider)
+}
@@ -0,0 +1,50 @@
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use url::Url;
+
+use super::TunnelProvider;
+
+/// Tunnel started outside of the application, its public URL is known upfront.
+pub struct StaticTunnel {
+    url: Url,
+}
+
+/// Server reachable only under its listen address.
+pub struct LocalTunnel;
+
+impl StaticTunnel {
+    pub fn new(url: Url) -> Self {
+        Self { url }
+    }
+}
+
+impl TunnelProvider for StaticTunnel {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    fn open<'a>(&'a mut self, _local_address: &'a str) -> BoxFuture<'a, anyhow::Result<Url>> {
+        future::ready(Ok(self.url.clone())).boxed()
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, anyhow::Result<()>> {
+        future::ready(Ok(())).boxed()
+    }
+}
+
+impl TunnelProvider for LocalTunnel {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn open<'a>(&'a mut self, local_address: &'a str) -> BoxFuture<'a, anyhow::Result<Url>> {
+        let url = Url::parse(&format!("http://{local_address}")).map_err(Into::into);
+        future::ready(url).boxed()
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, anyhow::Result<()>> {
+        future::ready(Ok(())).boxed()
+    }
+}
@@ -0,0 +1,240 @@
+use std::{process::Stdio, time::Duration};
+
+use anyhow::{anyhow, bail};
+use futures::{future::BoxFuture, FutureExt};
+use regex::Regex;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::timeout,
+};
+use url::Url;
+
+use super::TunnelProvider;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum ProcessTunnelKind {
+    Ngrok,
+    Cloudflared,
+    /// Reverse SSH tunnel to services like localhost.run or serveo.net
+    Ssh {
+        host: String,
+    },
+}
+
+/// Tunnel provided by an external binary. The process is spawned when the tunnel is opened,
+/// its public URL is discovered from the process output and the process is watched until closed.
+pub struct ProcessTunnel {
+    kind: ProcessTunnelKind,
+    binary: Option<String>,
+    supervisor: Option<(JoinHandle<()>, oneshot::Sender<()>)>,
+}
+
+impl ProcessTunnelKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ngrok => "ngrok",
+            Self::Cloudflared => "cloudflared",
+            Self::Ssh { .. } => "ssh",
+        }
+    }
+
+    fn args(&self, local_address: &str) -> Vec<String> {
+        match self {
+            Self::Ngrok => vec![
+                "http".into(),
+                local_address.into(),
+                "--log".into(),
+                "stdout".into(),
+                "--log-format".into(),
+                "logfmt".into(),
+            ],
+            Self::Cloudflared => vec![
+                "tunnel".into(),
+                "--no-autoupdate".into(),
+                "--url".into(),
+                format!("http://{local_address}"),
+            ],
+            Self::Ssh { host } => vec![
+                "-T".into(),
+                "-o".into(),
+                "StrictHostKeyChecking=accept-new".into(),
+                "-o".into(),
+                "ServerAliveInterval=30".into(),
+                "-R".into(),
+                format!("80:{local_address}"),
+                host.clone(),
+            ],
+        }
+    }
+
+    /// Pattern of the public URL in process output, the URL is the first capture group.
+    /// ngrok logs links to its dashboard in error lines, so only the logfmt `url` key is matched.
+    fn url_pattern(&self) -> anyhow::Result<Regex> {
+        let pattern = match self {
+            Self::Ngrok => r"\burl=(https://[\w.-]+\.ngrok[\w.-]*)",
+            Self::Cloudflared => r"(https://[\w-]+\.trycloudflare\.com)",
+            Self::Ssh { .. } => {
+                r"(https://[\w-]+\.(?:lhr\.life|localhost\.run|serveo\.net|serveousercontent\.com))"
+            }
+        };
+        Ok(Regex::new(pattern)?)
+    }
+}
+
+/// Finds public URL in the process output line.
+fn find_url<'a>(pattern: &Regex, line: &'a str) -> Option<&'a str> {
+    pattern
+        .captures(line)
+        .and_then(|captures| captures.get(1))
+        .map(|url| url.as_str())
+}
+
+impl ProcessTunnel {
+    pub fn new(kind: ProcessTunnelKind, binary: Option<String>) -> Self {
+        Self {
+            kind,
+            binary,
+            supervisor: None,
+        }
+    }
+
+    async fn start(&mut self, local_address: &str) -> anyhow::Result<Url> {
+        if self.supervisor.is_some() {
+            bail!("{} tunnel already opened", self.kind.name());
+        }
+
+        let name = self.kind.name();
+        let binary = self.binary.as_deref().unwrap_or(name);
+        log::info!("Starting {name} tunnel to {local_address}");
+
+        let mut child = Command::new(binary)
+            .args(self.kind.args(local_address))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Can not start '{binary}': {e}"))?;
+
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, output_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, output_tx));
+        }
+
+        let url_pattern = self.kind.url_pattern()?;
+        let discovered = timeout(STARTUP_TIMEOUT, async {
+            while let Some(line) = output_rx.recv().await {
+                log::debug!("{name}: {line}");
+                if let Some(url) = find_url(&url_pattern, &line) {
+                    return Some(url.to_string());
+                }
+            }
+            None
+        })
+        .await;
+
+        let url = match discovered {
+            Ok(Some(url)) => Url::parse(&url)?,
+            Ok(None) => {
+                let status = child.wait().await?;
+                bail!("{name} exited ({status}) before reporting public URL");
+            }
+            Err(_) => {
+                child.kill().await?;
+                bail!("{name} did not report public URL in {STARTUP_TIMEOUT:?}");
+            }
+        };
+        log::info!("{name} tunnel public URL: {url}");
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let handle = tokio::spawn(supervise(name, child, output_rx, kill_rx));
+        self.supervisor = Some((handle, kill_tx));
+
+        Ok(url)
+    }
+
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        if let Some((handle, kill_tx)) = self.supervisor.take() {
+            let _ = kill_tx.send(());
+            handle.await?;
+        }
+        Ok(())
+    }
+}
+
+impl TunnelProvider for ProcessTunnel {
+    fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn open<'a>(&'a mut self, local_address: &'a str) -> BoxFuture<'a, anyhow::Result<Url>> {
+        self.start(local_address).boxed()
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, anyhow::Result<()>> {
+        self.stop().boxed()
+    }
+}
+
+async fn forward_lines(output: impl AsyncRead + Unpin, tx: mpsc::UnboundedSender<String>) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tx.send(line).is_err() {
+            break;
+        }
+    }
+}
+
+async fn supervise(
+    name: &'static str,
+    mut child: Child,
+    mut output_rx: mpsc::UnboundedReceiver<String>,
+    mut kill_rx: oneshot::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            status = child.wait() => {
+                match status {
+                    Ok(status) => log::error!("{name} tunnel process exited unexpectedly: {status}"),
+                    Err(err) => log::error!("{name} tunnel process failed: {err}"),
+                }
+                break;
+            }
+            Some(line) = output_rx.recv() => log::debug!("{name}: {line}"),
+            _ = &mut kill_rx => {
+                if let Err(err) = child.kill().await {
+                    log::error!("Can not stop {name} tunnel process: {err}");
+                }
+                log::info!("{name} tunnel closed");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_ngrok_url() {
+        let pattern = ProcessTunnelKind::Ngrok.url_pattern().unwrap();
+
+        let error = r#"t=2024-04-10T10:00:00+0200 lvl=eror msg="failed to reconnect session" obj=tunnels.session err="authentication failed: Usage of ngrok requires a verified account and authtoken.\n\nSign up for an account: https://dashboard.ngrok.com/signup\nInstall your authtoken: https://dashboard.ngrok.com/get-started/your-authtoken\r\n\r\nERR_NGROK_4018\r\n""#;
+        assert_eq!(find_url(&pattern, error), None);
+
+        let started = r#"t=2024-04-10T10:00:01+0200 lvl=info msg="started tunnel" obj=tunnels name=command_line addr=http://localhost:8080 url=https://1a2b-83-1-2-3.ngrok-free.app"#;
+        assert_eq!(
+            find_url(&pattern, started),
+            Some("https://1a2b-83-1-2-3.ngrok-free.app")
+        );
+    }
+}
@@ -14,9 +14,25 @@ use qdrant_client::{
         VectorsConfig,
     },
 };
+use serde::de::DeserializeOwned;
 
 pub const EMBEDDING_MODEL: &str = "text-embedding-ada-002";
 
+/// Parses JSON object from LLM answer. Models often wrap JSON in Markdown code block
+/// or add a comment around it, so only the part between outer braces is parsed.
+///
+/// * `answer`: LLM answer
+pub(crate) fn parse_json_answer<T: DeserializeOwned>(answer: &str) -> anyhow::Result<T> {
+    let start = answer.find('{');
+    let end = answer.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &answer[start..=end],
+        _ => answer,
+    };
+
+    serde_json::from_str(json).map_err(|e| anyhow!("Can not parse JSON from LLM answer: {e}"))
+}
+
 pub(crate) async fn ask_llm(
     client: &Client<OpenAIConfig>,
     model: &str,
@@ -0,0 +1,294 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        ChatCompletionRequestMessageContentPart, ChatCompletionRequestMessageContentPartImageArgs,
+        ChatCompletionRequestMessageContentPartTextArgs, ChatCompletionRequestUserMessageArgs,
+        CreateChatCompletionRequestArgs, ImageUrlArgs, ImageUrlDetail,
+    },
+    Client,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Args, ValueEnum};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use crate::utils::parse_json_answer;
+
+pub const VISION_MODEL: &str = "gpt-4-vision-preview";
+/// Answer requested from the model when the image does not match the question.
+pub const ERROR_ANSWER: &str = "ERROR";
+/// Vision preview model returns only a few tokens when the limit is not set.
+const MAX_TOKENS: u16 = 1024;
+const REFUSAL_PREFIXES: [&str; 6] = [
+    "i'm sorry",
+    "i am sorry",
+    "i can't",
+    "i cannot",
+    "i'm unable",
+    "i am unable",
+];
+
+/// Image passed to vision model.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// Image available publicly, downloaded by OpenAI
+    Url(Url),
+    /// Local image, sent as base64 data URL
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+/// Vision model answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisionAnswer {
+    Answer(String),
+    /// Model answered `ERROR` or refused to answer
+    Rejected(String),
+}
+
+/// Asks questions about images.
+pub struct Vision {
+    client: Client<OpenAIConfig>,
+    detail: ImageDetail,
+}
+
+#[derive(Debug, Args)]
+pub struct DescribeImageArgs {
+    /// Image file paths or URLs
+    #[arg(required = true)]
+    pub images: Vec<String>,
+
+    /// Question about the images
+    #[arg(short, long, default_value = "Describe the image")]
+    pub prompt: String,
+
+    /// Image detail level
+    #[arg(short, long, value_enum, default_value_t = ImageDetail::High)]
+    pub detail: ImageDetail,
+
+    /// Ask for JSON object and print it formatted
+    #[arg(short, long)]
+    pub json: bool,
+}
+
+impl ImageSource {
+    /// Parses image URL or local file path.
+    ///
+    /// * `source`: `http(s)` or `data` URL, otherwise file path
+    pub fn parse(source: &str) -> Self {
+        match Url::parse(source) {
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "data") => Self::Url(url),
+            _ => Self::File(PathBuf::from(source)),
+        }
+    }
+
+    /// URL accepted by OpenAI API, local files are read and encoded as data URLs.
+    pub fn to_image_url(&self) -> anyhow::Result<String> {
+        match self {
+            Self::Url(url) => Ok(url.to_string()),
+            Self::File(path) => {
+                let mime = mime_guess::from_path(path)
+                    .first()
+                    .filter(|m| m.type_() == mime_guess::mime::IMAGE)
+                    .ok_or(anyhow!("{} is not a supported image", path.display()))?;
+                let bytes = std::fs::read(path)
+                    .map_err(|e| anyhow!("Can not read {}: {e}", path.display()))?;
+                Ok(format!("data:{mime};base64,{}", STANDARD.encode(bytes)))
+            }
+        }
+    }
+}
+
+impl From<ImageDetail> for ImageUrlDetail {
+    fn from(detail: ImageDetail) -> Self {
+        match detail {
+            ImageDetail::Auto => Self::Auto,
+            ImageDetail::Low => Self::Low,
+            ImageDetail::High => Self::High,
+        }
+    }
+}
+
+impl VisionAnswer {
+    /// Recognizes `ERROR` answers and refusals.
+    ///
+    /// * `answer`: vision model answer
+    pub fn from_answer(answer: String) -> Self {
+        let normalized = answer
+            .trim()
+            .trim_end_matches(['.', '!'])
+            .trim_matches(['"', '\''])
+            .to_lowercase();
+        let rejected = normalized == ERROR_ANSWER.to_lowercase()
+            || REFUSAL_PREFIXES.iter().any(|p| normalized.starts_with(p));
+
+        match rejected {
+            true => Self::Rejected(answer),
+            false => Self::Answer(answer),
+        }
+    }
+}
+
+impl Vision {
+    pub fn new(detail: ImageDetail) -> Self {
+        Self {
+            client: Client::with_config(OpenAIConfig::default()),
+            detail,
+        }
+    }
+
+    /// Asks question about images. The model is instructed to answer `ERROR`
+    /// when images do not show what the question is about.
+    ///
+    /// * `images`: images attached to the question
+    /// * `question`: question about the images
+    pub async fn ask(
+        &self,
+        images: &[ImageSource],
+        question: &str,
+    ) -> anyhow::Result<VisionAnswer> {
+        let prompt = [
+            question,
+            &format!("If the images do not show what the question is about, answer {ERROR_ANSWER}"),
+            "Answer concisely as possible",
+        ]
+        .join("\n");
+        let answer = self.request(images, &prompt).await?;
+
+        Ok(VisionAnswer::from_answer(answer))
+    }
+
+    /// Asks question about images and parses JSON object from the answer.
+    /// Returns `None` when the model rejected the question.
+    ///
+    /// * `images`: images attached to the question
+    /// * `question`: question about the images, should describe expected JSON object
+    pub async fn ask_json<T: DeserializeOwned>(
+        &self,
+        images: &[ImageSource],
+        question: &str,
+    ) -> anyhow::Result<Option<T>> {
+        let prompt = [
+            question,
+            "Return only JSON object, without Markdown and any comment",
+            &format!("If the images do not show what the question is about, answer {ERROR_ANSWER}"),
+        ]
+        .join("\n");
+        let answer = self.request(images, &prompt).await?;
+
+        match VisionAnswer::from_answer(answer) {
+            VisionAnswer::Answer(answer) => parse_json_answer(&answer).map(Some),
+            VisionAnswer::Rejected(_) => Ok(None),
+        }
+    }
+
+    async fn request(&self, images: &[ImageSource], prompt: &str) -> anyhow::Result<String> {
+        let mut content: Vec<ChatCompletionRequestMessageContentPart> =
+            vec![ChatCompletionRequestMessageContentPartTextArgs::default()
+                .text(prompt)
+                .build()?
+                .into()];
+        for image in images {
+            let image_url = ImageUrlArgs::default()
+                .url(image.to_image_url()?)
+                .detail(ImageUrlDetail::from(self.detail))
+                .build()?;
+            content.push(
+                ChatCompletionRequestMessageContentPartImageArgs::default()
+                    .image_url(image_url)
+                    .build()?
+                    .into(),
+            );
+        }
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(VISION_MODEL)
+            .max_tokens(MAX_TOKENS)
+            .messages([ChatCompletionRequestUserMessageArgs::default()
+                .content(content)
+                .build()?
+                .into()])
+            .build()?;
+
+        log::info!(
+            "Question to {VISION_MODEL} about {} image(s): {prompt}",
+            images.len()
+        );
+        let response = self.client.chat().create(request).await?;
+        let answer = response
+            .choices
+            .into_iter()
+            .find_map(|c| c.message.content)
+            .ok_or(anyhow!("{VISION_MODEL} response do not contain answer."))?;
+        log::info!("{VISION_MODEL} answer: {answer}");
+
+        Ok(answer)
+    }
+}
+
+/// Asks vision model about images and prints the answer.
+///
+/// * `args`: describe-image command arguments
+pub async fn run(args: DescribeImageArgs) -> anyhow::Result<()> {
+    let vision = Vision::new(args.detail);
+    let images = args
+        .images
+        .iter()
+        .map(|s| ImageSource::parse(s))
+        .collect::<Vec<_>>();
+
+    if args.json {
+        match vision
+            .ask_json::<serde_json::Value>(&images, &args.prompt)
+            .await?
+        {
+            Some(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+            None => println!("{ERROR_ANSWER}"),
+        }
+        return Ok(());
+    }
+
+    match vision.ask(&images, &args.prompt).await? {
+        VisionAnswer::Answer(answer) => println!("{answer}"),
+        VisionAnswer::Rejected(answer) => {
+            log::warn!("Model rejected the question: {answer}");
+            println!("{ERROR_ANSWER}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_rejected_answers() {
+        assert_eq!(
+            VisionAnswer::from_answer("red".into()),
+            VisionAnswer::Answer("red".into())
+        );
+        assert!(matches!(
+            VisionAnswer::from_answer(" ERROR.\n".into()),
+            VisionAnswer::Rejected(_)
+        ));
+        assert!(matches!(
+            VisionAnswer::from_answer("I'm sorry, I can't help with that.".into()),
+            VisionAnswer::Rejected(_)
+        ));
+        assert_eq!(
+            ImageSource::parse("images/gnome.png"),
+            ImageSource::File("images/gnome.png".into())
+        );
+    }
+}